@@ -0,0 +1,128 @@
+use strum_macros::{EnumIter, EnumMessage, EnumString};
+
+#[repr(i32)]
+#[derive(
+    serde_derive::Serialize,
+    serde_derive::Deserialize,
+    PartialEq,
+    Clone,
+    Copy,
+    EnumString,
+    EnumIter,
+    EnumMessage,
+    Debug,
+    Default,
+)]
+pub enum Area {
+    #[default]
+    #[strum(serialize = "-2")]
+    Unknown = -2,
+    #[strum(serialize = "Menu", serialize = "-1", ascii_case_insensitive)]
+    Menu = -1,
+    #[strum(serialize = "Prologue", serialize = "0", ascii_case_insensitive)]
+    Prologue = 0,
+    #[strum(serialize = "ForsakenCity", serialize = "1", ascii_case_insensitive)]
+    ForsakenCity = 1,
+    #[strum(serialize = "OldSite", serialize = "2", ascii_case_insensitive)]
+    OldSite = 2,
+    #[strum(serialize = "CelestialResort", serialize = "3", ascii_case_insensitive)]
+    CelestialResort = 3,
+    #[strum(serialize = "GoldenRidge", serialize = "4", ascii_case_insensitive)]
+    GoldenRidge = 4,
+    #[strum(serialize = "MirrorTemple", serialize = "5", ascii_case_insensitive)]
+    MirrorTemple = 5,
+    #[strum(serialize = "Reflection", serialize = "6", ascii_case_insensitive)]
+    Reflection = 6,
+    #[strum(serialize = "TheSummit", serialize = "7", ascii_case_insensitive)]
+    TheSummit = 7,
+    #[strum(serialize = "Epilogue", serialize = "8", ascii_case_insensitive)]
+    Epilogue = 8,
+    #[strum(serialize = "Core", serialize = "9", ascii_case_insensitive)]
+    Core = 9,
+    #[strum(serialize = "Farewell", serialize = "10", ascii_case_insensitive)]
+    Farewell = 10,
+}
+
+#[repr(i32)]
+#[derive(
+    serde_derive::Serialize,
+    serde_derive::Deserialize,
+    PartialEq,
+    Clone,
+    Copy,
+    EnumString,
+    EnumIter,
+    EnumMessage,
+    Debug,
+    Default,
+)]
+pub enum AreaMode {
+    #[default]
+    #[strum(serialize = "-2")]
+    Unknown = -2,
+    #[strum(serialize = "-1")]
+    None = -1,
+    #[strum(serialize = "ASide", serialize = "0", ascii_case_insensitive)]
+    ASide = 0,
+    #[strum(serialize = "BSide", serialize = "1", ascii_case_insensitive)]
+    BSide = 1,
+    #[strum(serialize = "CSide", serialize = "2", ascii_case_insensitive)]
+    CSide = 2,
+}
+
+impl TryFrom<i32> for Area {
+    type Error = anyhow::Error;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            -1 => Ok(Area::Menu),
+            0 => Ok(Area::Prologue),
+            1 => Ok(Area::ForsakenCity),
+            2 => Ok(Area::OldSite),
+            3 => Ok(Area::CelestialResort),
+            4 => Ok(Area::GoldenRidge),
+            5 => Ok(Area::MirrorTemple),
+            6 => Ok(Area::Reflection),
+            7 => Ok(Area::TheSummit),
+            8 => Ok(Area::Epilogue),
+            9 => Ok(Area::Core),
+            10 => Ok(Area::Farewell),
+            _ => Ok(Area::Unknown),
+        }
+    }
+}
+
+impl TryFrom<i32> for AreaMode {
+    type Error = anyhow::Error;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            -1 => Ok(AreaMode::None),
+            0 => Ok(AreaMode::ASide),
+            1 => Ok(AreaMode::BSide),
+            2 => Ok(AreaMode::CSide),
+            _ => Ok(AreaMode::Unknown),
+        }
+    }
+}
+
+impl Area {
+    /// The name of the chapter as shown in the game.
+    pub fn name(self) -> &'static str {
+        match self {
+            Area::Prologue => "Prologue",
+            Area::ForsakenCity => "Forsaken City",
+            Area::OldSite => "Old Site",
+            Area::CelestialResort => "Celestial Resort",
+            Area::GoldenRidge => "Golden Ridge",
+            Area::MirrorTemple => "Mirror Temple",
+            Area::Reflection => "Reflection",
+            Area::TheSummit => "The Summit",
+            Area::Epilogue => "Epilogue",
+            Area::Core => "Core",
+            Area::Farewell => "Farewell",
+            Area::Menu => "Menu",
+            Area::Unknown => "Unknown",
+        }
+    }
+}
@@ -0,0 +1,63 @@
+#![forbid(unsafe_code)]
+//! Reads the state of Celeste and writes it on the standard output, one JSON line per change.
+//!
+//! It's the only part of LinSplit that needs the permission to read the game's memory, so it
+//! can be given `cap_sys_ptrace` while `linsplit` runs unprivileged with `--helper`.
+use anyhow::{Result, anyhow};
+use clap::Parser;
+use log::LevelFilter;
+use std::io::Write;
+use std::time::Duration;
+
+use linsplit::memory_reader::game_data::GameData;
+use linsplit::memory_reader::helper_reader::HelperMessage;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Args {
+    /// Path to the folder containing the save data (files like 0.celeste).
+    #[arg(short = 'f', long = "save-location", value_name = "PATH")]
+    save_location: String,
+
+    /// How many times per second the game's memory is read.
+    #[arg(long = "polling-rate", value_name = "HZ", default_value_t = 1000)]
+    polling_rate: u32,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    // The standard output is for the state, the logs go to the standard error like linsplit's
+    env_logger::Builder::new()
+        .filter_level(LevelFilter::Warn)
+        .filter_module("linsplit", LevelFilter::Info)
+        .filter_module("linsplit_helper", LevelFilter::Info)
+        .format_timestamp_millis()
+        .parse_default_env()
+        .try_init()
+        .map_err(|e| anyhow!("couldn't start logging: {e}"))?;
+    let polling_interval = Duration::from_secs(1) / args.polling_rate.max(1);
+    let mut game_data = GameData::new(args.save_location, None).await;
+    let mut last_line = String::new();
+    loop {
+        let message = match game_data.update() {
+            Ok(()) => HelperMessage::State(game_data.state.clone()),
+            // Exiting lets linsplit start a new helper, which will find the game again
+            Err(_) if game_data.should_reattach() => return Ok(()),
+            Err(error) => HelperMessage::Error(error.to_string()),
+        };
+        let line = serde_json::to_string(&message)?;
+        if line != last_line {
+            let mut stdout = std::io::stdout().lock();
+            if writeln!(stdout, "{line}")
+                .and_then(|_| stdout.flush())
+                .is_err()
+            {
+                // linsplit stopped
+                return Ok(());
+            }
+            last_line = line;
+        }
+        tokio::time::sleep(polling_interval).await;
+    }
+}
@@ -0,0 +1,246 @@
+use anyhow::{Result, anyhow};
+use expand_tilde::expand_tilde;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::event_stream::EventOutput;
+use crate::split_reader::SettingsOverrides;
+
+const DEFAULT_PROFILE: &str = "default";
+pub const DEFAULT_SAVE_LOCATION: &str = "~/.local/share/Celeste/Saves/";
+
+/// Backend that receives the split commands.
+#[derive(serde_derive::Deserialize, clap::ValueEnum, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TimerBackend {
+    #[default]
+    #[value(name = "livesplitone")]
+    LiveSplitOne,
+}
+
+/// What to do when the game time goes backwards, like when a savestate is loaded.
+#[derive(serde_derive::Deserialize, clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeRegressionPolicy {
+    /// Send the time as it is.
+    #[default]
+    Ignore,
+    /// Keep the timer where it was until the game time catches up.
+    Clamp,
+    /// Reset the run.
+    Reset,
+    /// Keep timing, but leave the attempt out of the statistics.
+    Practice,
+}
+
+/// Contents of `config.toml` (or `config.json`): a set of named profiles.
+#[derive(serde_derive::Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    #[serde(default)]
+    profiles: HashMap<String, Profile>,
+}
+
+/// Settings that can be given either in a profile of the config file or on the command line.
+#[derive(serde_derive::Deserialize, Default, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub splits: Option<String>,
+    pub timer: Option<TimerBackend>,
+    pub address: Option<String>,
+    pub port: Option<u16>,
+    pub save_location: Option<String>,
+    /// How many times per second the game's memory is read.
+    pub polling_rate: Option<u32>,
+    pub forbid_assists: Option<bool>,
+    pub time_regression: Option<TimeRegressionPolicy>,
+    /// File the room times of per-room runs are written to, as CSV or JSON.
+    pub room_times: Option<String>,
+    /// Where to write the JSON events to, `stdout` or `unix:PATH`.
+    pub events: Option<String>,
+    /// Address of the HTTP server serving the game's state, like `127.0.0.1:8080`.
+    pub http: Option<String>,
+    /// Folder to write text files for OBS to.
+    pub obs_dir: Option<String>,
+    /// Format of the OBS text files, by file name.
+    pub obs_formats: HashMap<String, String>,
+    /// Path to `linsplit-helper`, to read the game's memory through it.
+    pub helper: Option<String>,
+    /// File to also write the logs to.
+    pub log_file: Option<String>,
+    pub autosplitter: SettingsOverrides,
+}
+
+/// Settings of a run, once the command line and the config file have been merged.
+#[derive(Debug)]
+pub struct Options {
+    pub splits: String,
+    pub timer: TimerBackend,
+    pub address: String,
+    pub save_location: String,
+    pub polling_interval: Duration,
+    pub forbid_assists: bool,
+    pub time_regression: TimeRegressionPolicy,
+    pub room_times: Option<PathBuf>,
+    pub events: Option<EventOutput>,
+    pub http: Option<String>,
+    pub obs_dir: Option<PathBuf>,
+    pub obs_formats: HashMap<String, String>,
+    pub helper: Option<PathBuf>,
+    pub overrides: SettingsOverrides,
+}
+
+/// How to read the game's state.
+#[derive(Debug)]
+pub struct ReaderOptions {
+    pub save_location: String,
+    pub polling_interval: Duration,
+    pub helper: Option<PathBuf>,
+}
+
+/// `$XDG_CONFIG_HOME/linsplit`, or `~/.config/linsplit` if it isn't set.
+pub fn config_dir() -> Result<PathBuf> {
+    match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => Ok(PathBuf::from(dir).join("linsplit")),
+        _ => Ok(expand_tilde("~/.config/linsplit")?.into_owned()),
+    }
+}
+
+/// `$XDG_DATA_HOME/linsplit`, or `~/.local/share/linsplit` if it isn't set.
+pub fn data_dir() -> Result<PathBuf> {
+    match std::env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => Ok(PathBuf::from(dir).join("linsplit")),
+        _ => Ok(expand_tilde("~/.local/share/linsplit")?.into_owned()),
+    }
+}
+
+impl ConfigFile {
+    /// Reads the config file at `path`, or the default one. A missing default config file
+    /// is the same as an empty one.
+    pub fn load(path: Option<&str>) -> Result<Self> {
+        let path = match path {
+            Some(path) => expand_tilde(path)?.into_owned(),
+            None => {
+                let dir = config_dir()?;
+                match ["config.toml", "config.json"]
+                    .iter()
+                    .map(|name| dir.join(name))
+                    .find(|path| path.exists())
+                {
+                    Some(path) => path,
+                    None => return Ok(Self::default()),
+                }
+            }
+        };
+        let text = fs::read_to_string(&path)
+            .map_err(|e| anyhow!("couldn't read {}: {e}", path.display()))?;
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            serde_json::from_str(&text).map_err(|e| anyhow!("in {}: {e}", path.display()))
+        } else {
+            toml::from_str(&text).map_err(|e| anyhow!("in {}: {e}", path.display()))
+        }
+    }
+
+    /// Returns the profile called `name`, or the `default` profile if no name is given.
+    pub fn profile(&self, name: Option<&str>) -> Result<Profile> {
+        match name {
+            Some(name) => self
+                .profiles
+                .get(name)
+                .cloned()
+                .ok_or_else(|| anyhow!("no profile named \"{name}\" in the config file")),
+            None => Ok(self
+                .profiles
+                .get(DEFAULT_PROFILE)
+                .cloned()
+                .unwrap_or_default()),
+        }
+    }
+}
+
+impl Profile {
+    /// Fills the settings missing from `self` with the ones from `fallback`.
+    pub fn or(self, fallback: Profile) -> Profile {
+        Profile {
+            splits: self.splits.or(fallback.splits),
+            timer: self.timer.or(fallback.timer),
+            address: self.address.or(fallback.address),
+            port: self.port.or(fallback.port),
+            save_location: self.save_location.or(fallback.save_location),
+            polling_rate: self.polling_rate.or(fallback.polling_rate),
+            forbid_assists: self.forbid_assists.or(fallback.forbid_assists),
+            time_regression: self.time_regression.or(fallback.time_regression),
+            room_times: self.room_times.or(fallback.room_times),
+            events: self.events.or(fallback.events),
+            http: self.http.or(fallback.http),
+            obs_dir: self.obs_dir.or(fallback.obs_dir),
+            obs_formats: fallback
+                .obs_formats
+                .into_iter()
+                .chain(self.obs_formats)
+                .collect(),
+            helper: self.helper.or(fallback.helper),
+            log_file: self.log_file.or(fallback.log_file),
+            autosplitter: self.autosplitter.or(fallback.autosplitter),
+        }
+    }
+
+    /// The settings needed to read the game, for the commands that don't need a splits file.
+    pub fn reader(&self) -> Result<ReaderOptions> {
+        let polling_rate = self.polling_rate.unwrap_or(1000);
+        if polling_rate == 0 {
+            return Err(anyhow!("the polling rate must be at least 1"));
+        }
+        Ok(ReaderOptions {
+            save_location: self
+                .save_location
+                .clone()
+                .unwrap_or(String::from(DEFAULT_SAVE_LOCATION)),
+            polling_interval: Duration::from_secs(1) / polling_rate,
+            helper: self
+                .helper
+                .as_ref()
+                .map(|path| expand_tilde(path).map(|path| path.into_owned()))
+                .transpose()?,
+        })
+    }
+
+    /// Applies the default values and checks that the required settings are there.
+    pub fn resolve(self) -> Result<Options> {
+        let reader = self.reader()?;
+        let splits = self.splits.ok_or(anyhow!(
+            "no splits file given, use --splits or set `splits` in a profile"
+        ))?;
+        Ok(Options {
+            splits: expand_tilde(&splits)?.to_string_lossy().into_owned(),
+            timer: self.timer.unwrap_or_default(),
+            address: format!(
+                "{}:{}",
+                self.address.as_deref().unwrap_or("127.0.0.1"),
+                self.port.unwrap_or(51000)
+            ),
+            save_location: reader.save_location,
+            polling_interval: reader.polling_interval,
+            forbid_assists: self.forbid_assists.unwrap_or(false),
+            time_regression: self.time_regression.unwrap_or_default(),
+            room_times: self
+                .room_times
+                .map(|path| expand_tilde(&path).map(|path| path.into_owned()))
+                .transpose()?,
+            events: self.events.as_deref().map(str::parse).transpose()?,
+            http: self.http,
+            obs_dir: self
+                .obs_dir
+                .map(|path| expand_tilde(&path).map(|path| path.into_owned()))
+                .transpose()?,
+            obs_formats: self.obs_formats,
+            helper: reader.helper,
+            overrides: self.autosplitter,
+        })
+    }
+}
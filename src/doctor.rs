@@ -0,0 +1,220 @@
+use anyhow::{Result, anyhow};
+use std::fs;
+
+use crate::config::{DEFAULT_SAVE_LOCATION, Profile};
+use crate::memory_reader::game_data::GameData;
+use crate::save_reader::{SaveData, save_files};
+use crate::split_reader::SplitData;
+
+/// Bit of `CAP_SYS_PTRACE` in the capability sets.
+const CAP_SYS_PTRACE: u32 = 19;
+
+/// Result of the checks, printed as they go.
+#[derive(Default)]
+struct Report {
+    problems: usize,
+}
+
+impl Report {
+    fn ok(&self, message: &str) {
+        println!("[ok] {message}");
+    }
+
+    fn info(&self, message: &str) {
+        println!("[--] {message}");
+    }
+
+    /// A failed check, with the commands or steps that fix it.
+    fn problem(&mut self, message: &str, fixes: &[&str]) {
+        self.problems += 1;
+        println!("[!!] {message}");
+        for fix in fixes {
+            println!("       {fix}");
+        }
+    }
+}
+
+/// A field of `/proc/self/status`, like `Uid` or `CapEff`.
+fn status_field(name: &str) -> Option<String> {
+    fs::read_to_string("/proc/self/status")
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+        .map(|value| value.trim().to_string())
+}
+
+/// `linsplit doctor`: checks everything LinSplit needs and explains how to fix what's wrong.
+pub async fn run(profile: &Profile) -> Result<()> {
+    let mut report = Report::default();
+
+    println!("Permissions");
+    let root = status_field("Uid")
+        .and_then(|uids| uids.split_whitespace().nth(1).map(|uid| uid == "0"))
+        .unwrap_or(false);
+    let ptrace_capability = status_field("CapEff")
+        .and_then(|caps| u64::from_str_radix(&caps, 16).ok())
+        .is_some_and(|caps| caps & (1 << CAP_SYS_PTRACE) != 0);
+    // Without Yama, any process can read the memory of the processes of the same user
+    let ptrace_scope = fs::read_to_string("/proc/sys/kernel/yama/ptrace_scope")
+        .ok()
+        .and_then(|scope| scope.trim().parse::<u8>().ok())
+        .unwrap_or(0);
+    if root {
+        report.ok("running as root");
+    } else {
+        report.info("not running as root");
+    }
+    if ptrace_capability {
+        report.ok("the cap_sys_ptrace capability is set");
+    } else {
+        report.info("the cap_sys_ptrace capability isn't set");
+    }
+    match ptrace_scope {
+        0 => report.ok("kernel.yama.ptrace_scope is 0"),
+        scope => report.info(&format!("kernel.yama.ptrace_scope is {scope}")),
+    }
+    let can_read = match ptrace_scope {
+        0 => true,
+        1 | 2 => root || ptrace_capability,
+        _ => false,
+    };
+    if !can_read {
+        let executable = std::env::current_exe()
+            .map(|path| path.display().to_string())
+            .unwrap_or(String::from("linsplit"));
+        report.problem(
+            "LinSplit can't read the memory of other processes",
+            &[
+                "Fix it with one of:",
+                "  sudo sysctl -w kernel.yama.ptrace_scope=0",
+                &format!("  sudo setcap cap_sys_ptrace=eip {executable}"),
+                "  running linsplit as root (give the save folder with -f)",
+                "  sudo setcap cap_sys_ptrace=eip <path to linsplit-helper>, then linsplit --helper <that path>",
+            ],
+        );
+    }
+
+    println!("Game");
+    let processes: Vec<_> = procfs::process::all_processes()
+        .map_err(|e| anyhow!("can't read /proc: {e}"))?
+        .filter_map(|process| process.ok())
+        .filter_map(|process| {
+            let comm = process.stat().ok()?.comm;
+            comm.contains("Celeste").then_some((process, comm))
+        })
+        .collect();
+    if processes.is_empty() {
+        report.problem("no Celeste process is running", &["Start the game first."]);
+    }
+    for (process, comm) in &processes {
+        match process.mem() {
+            Ok(_) => report.ok(&format!("{comm} (pid {}) is readable", process.pid)),
+            Err(error) => report.problem(
+                &format!("{comm} (pid {}) can't be read: {error}", process.pid),
+                &["See the permissions above."],
+            ),
+        }
+    }
+
+    println!("Save files");
+    let save_location = profile
+        .save_location
+        .as_deref()
+        .unwrap_or(DEFAULT_SAVE_LOCATION);
+    match save_files(save_location) {
+        Err(error) => report.problem(
+            &format!("the save folder {save_location} can't be read: {error}"),
+            &["Give the folder containing files like 0.celeste with -f."],
+        ),
+        Ok(files) if files.is_empty() => report.problem(
+            &format!("the save folder {save_location} has no save file"),
+            &["Give the folder containing files like 0.celeste with -f."],
+        ),
+        Ok(files) => {
+            for file in files {
+                match SaveData::read(&file) {
+                    Ok(save) => {
+                        let completed = save
+                            .areas
+                            .iter()
+                            .flat_map(|area| &area.modes)
+                            .filter(|mode| mode.completed)
+                            .count();
+                        let modes: String = [
+                            (save.assist_mode, ", assist mode"),
+                            (save.variant_mode, ", variant mode"),
+                            (save.cheat_mode, ", cheat mode"),
+                        ]
+                        .iter()
+                        .filter(|(on, _)| *on)
+                        .map(|(_, name)| *name)
+                        .collect();
+                        report.ok(&format!(
+                            "{} ({} deaths, {} berries, {completed} sides completed{modes})",
+                            file.display(),
+                            save.total_deaths,
+                            save.total_strawberries
+                        ))
+                    }
+                    Err(error) => report.problem(
+                        &format!("{} can't be parsed: {error}", file.display()),
+                        &["It won't be used to find vanilla Celeste."],
+                    ),
+                }
+            }
+        }
+    }
+
+    println!("Autosplitter data");
+    if !processes.is_empty() {
+        let (vanilla, everest) = GameData::probe_readers(save_location).await;
+        match (vanilla, everest) {
+            (_, Ok(true)) => report.ok("found the Everest autosplitter info"),
+            (Ok(true), _) => report.ok("found the vanilla autosplitter info"),
+            (_, Err(error)) => report.problem(
+                &format!("the Everest autosplitter info can't be used: {error}"),
+                &["Update LinSplit, or Everest if LinSplit is already up to date."],
+            ),
+            (Err(error), _) => report.problem(
+                &format!("the game's memory couldn't be searched: {error}"),
+                &["See the permissions above."],
+            ),
+            (Ok(false), Ok(false)) => report.problem(
+                "neither the vanilla nor the Everest autosplitter info were found",
+                &[
+                    "With vanilla Celeste, be in the map of a save file with some playtime.",
+                    "With Everest, check that it's up to date.",
+                ],
+            ),
+        }
+    } else {
+        report.info("skipped, Celeste isn't running");
+    }
+
+    println!("Splits");
+    match &profile.splits {
+        None => report.info("no splits file given, use -s to check one"),
+        Some(splits) => match expand_tilde::expand_tilde(splits)
+            .map_err(anyhow::Error::from)
+            .and_then(|path| SplitData::read_splits(&path.to_string_lossy()))
+        {
+            Ok(data) => report.ok(&format!(
+                "{splits}: {} autosplitter splits, timed as {:?}",
+                data.splits.len(),
+                data.run_kind
+            )),
+            Err(error) => report.problem(
+                &format!("{splits} can't be read: {error}"),
+                &["Check that it's a splits file (.lss) saved by LiveSplit or LiveSplit One."],
+            ),
+        },
+    }
+
+    match report.problems {
+        0 => {
+            println!("Everything looks fine!");
+            Ok(())
+        }
+        problems => Err(anyhow!("found {problems} problem(s)")),
+    }
+}
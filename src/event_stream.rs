@@ -0,0 +1,263 @@
+use anyhow::{Result, anyhow};
+use serde_json::{Map, Value};
+use std::fs;
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::UnixListener;
+use tokio::sync::broadcast;
+
+use crate::memory_reader::game_data::GameState;
+
+/// Fields of [`GameState`] that change all the time, and aren't reported as changes.
+const IGNORED_FIELDS: [&str; 2] = ["game_time", "level_time"];
+
+/// How many events a slow consumer can be behind before losing some.
+const CAPACITY: usize = 1024;
+
+/// Where the events are written to.
+#[derive(Clone, Debug)]
+pub enum EventOutput {
+    Stdout,
+    /// A Unix socket, several consumers can connect to it.
+    Unix(PathBuf),
+}
+
+impl FromStr for EventOutput {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once(':') {
+            _ if s == "stdout" => Ok(EventOutput::Stdout),
+            Some(("unix", path)) if !path.is_empty() => Ok(EventOutput::Unix(
+                expand_tilde::expand_tilde(path)?.into_owned(),
+            )),
+            _ => Err(anyhow!(
+                "invalid events output \"{s}\", expected stdout or unix:PATH"
+            )),
+        }
+    }
+}
+
+/// An event of the stream, written as a line of JSON.
+#[derive(serde_derive::Serialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    /// Fields of the game's state that changed, with their new value.
+    State { changes: Map<String, Value> },
+    /// LinSplit decided to split.
+    Split { index: i32, segment: Option<String> },
+    /// LinSplit decided to reset the run.
+    Reset { index: i32 },
+    /// An event received from the timer.
+    Timer { event: String },
+}
+
+/// Sends the events to every consumer of the stream.
+pub struct EventStream {
+    sender: broadcast::Sender<String>,
+    /// Fields of the last state emitted, that the next states are compared with.
+    last_state: Mutex<Option<Map<String, Value>>>,
+}
+
+impl EventStream {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        EventStream {
+            sender,
+            last_state: Mutex::new(None),
+        }
+    }
+
+    /// Starts writing the events to `output`.
+    pub fn write_to(&self, output: &EventOutput) -> Result<()> {
+        match output {
+            EventOutput::Stdout => {
+                let receiver = self.subscribe();
+                tokio::spawn(async move {
+                    let _ = write_events(receiver, tokio::io::stdout()).await;
+                });
+            }
+            EventOutput::Unix(path) => {
+                remove_stale_socket(path)?;
+                let listener = UnixListener::bind(path)
+                    .map_err(|e| anyhow!("couldn't listen on {}: {e}", path.display()))?;
+                let sender = self.sender.clone();
+                tokio::spawn(async move {
+                    while let Ok((stream, _)) = listener.accept().await {
+                        let receiver = sender.subscribe();
+                        tokio::spawn(async move {
+                            let _ = write_events(receiver, stream).await;
+                        });
+                    }
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// The events emitted from now on, as lines of JSON.
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.sender.subscribe()
+    }
+
+    pub fn emit(&self, event: StreamEvent) {
+        if let Ok(line) = serde_json::to_string(&event) {
+            // Fails only when nobody is listening
+            let _ = self.sender.send(line);
+        }
+    }
+
+    /// Emits the fields of `state` that changed since the last state emitted, when someone
+    /// is listening.
+    pub fn emit_state(&self, state: &GameState) {
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+        let Ok(Value::Object(fields)) = serde_json::to_value(state) else {
+            return;
+        };
+        let mut last_state = self
+            .last_state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let changes = state_changes(last_state.as_ref(), &fields);
+        *last_state = Some(fields);
+        drop(last_state);
+        if !changes.is_empty() {
+            self.emit(StreamEvent::State { changes });
+        }
+    }
+
+    /// Waits until every consumer received the events emitted so far.
+    pub async fn flush(&self) {
+        while !self.sender.is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+}
+
+async fn write_events(
+    mut receiver: broadcast::Receiver<String>,
+    mut writer: impl AsyncWriteExt + Unpin,
+) -> Result<()> {
+    loop {
+        match receiver.recv().await {
+            Ok(line) => {
+                writer.write_all(line.as_bytes()).await?;
+                writer.write_all(b"\n").await?;
+                writer.flush().await?;
+            }
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+/// Removes the socket a previous session left at `path`, that would prevent binding. Anything
+/// else, like a socket still in use or a regular file, is kept and is an error.
+fn remove_stale_socket(path: &Path) -> Result<()> {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return Ok(());
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(anyhow!(
+            "{} already exists and isn't a socket",
+            path.display()
+        ));
+    }
+    if UnixStream::connect(path).is_ok() {
+        return Err(anyhow!(
+            "{} is already used by another program",
+            path.display()
+        ));
+    }
+    fs::remove_file(path).map_err(|e| anyhow!("couldn't remove {}: {e}", path.display()))
+}
+
+/// The fields of `fields` that are different in `previous`, or all of them if there's no
+/// previous state.
+fn state_changes(
+    previous: Option<&Map<String, Value>>,
+    fields: &Map<String, Value>,
+) -> Map<String, Value> {
+    fields
+        .iter()
+        .filter(|(name, value)| {
+            !IGNORED_FIELDS.contains(&name.as_str())
+                && previous.is_none_or(|previous| previous.get(*name) != Some(*value))
+        })
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::split_reader::Area;
+    use std::os::unix::net::UnixListener as StdUnixListener;
+
+    fn fields(state: &GameState) -> Map<String, Value> {
+        match serde_json::to_value(state) {
+            Ok(Value::Object(fields)) => fields,
+            _ => panic!("a state is an object"),
+        }
+    }
+
+    #[test]
+    fn only_changed_fields_are_reported() {
+        let previous = GameState::default();
+        let state = GameState {
+            area_id: Area::OldSite,
+            level_name: String::from("a-00"),
+            game_time: 12.,
+            level_time: 3.,
+            ..GameState::default()
+        };
+        let changes = state_changes(Some(&fields(&previous)), &fields(&state));
+        let mut names: Vec<&str> = changes.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, ["area_id", "level_name"]);
+        assert_eq!(changes["area_id"], "OldSite");
+    }
+
+    #[test]
+    fn every_field_is_reported_at_first() {
+        let state = GameState::default();
+        let changes = state_changes(None, &fields(&state));
+        assert!(changes.contains_key("area_id"));
+        assert!(!changes.contains_key("game_time"));
+    }
+
+    fn temporary_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("linsplit-{}-{name}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn regular_files_are_not_removed() {
+        let path = temporary_path("file");
+        fs::write(&path, "keep me").unwrap();
+        assert!(remove_stale_socket(&path).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "keep me");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn only_stale_sockets_are_removed() {
+        let path = temporary_path("socket");
+        let listener = StdUnixListener::bind(&path).unwrap();
+        assert!(remove_stale_socket(&path).is_err());
+        assert!(path.exists());
+        drop(listener);
+        remove_stale_socket(&path).unwrap();
+        assert!(!path.exists());
+        // Nothing to remove
+        remove_stale_socket(&path).unwrap();
+    }
+}
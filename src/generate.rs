@@ -0,0 +1,428 @@
+use anyhow::{Result, anyhow};
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::split_reader::checkpoints::CHECKPOINTS;
+use crate::split_reader::{Area, AreaMode, Split, SplitData};
+
+/// A category LinSplit can write starter splits for.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Category {
+    /// Prologue to The Summit
+    #[value(name = "any%")]
+    Any,
+    /// Prologue to Core
+    TrueEnding,
+    /// Prologue to Core, splitting on completing each chapter with all of its red berries
+    AllRedBerries,
+    /// Every side of every chapter, and Farewell
+    #[value(name = "100%")]
+    OneHundred,
+    /// Every chapter, from Prologue to Farewell
+    AllChapters,
+    AllASides,
+    AllBSides,
+    AllCSides,
+    /// A single chapter, given with --chapter and --side
+    Il,
+}
+
+/// What to generate, from the command line.
+pub struct Request {
+    pub category: Category,
+    pub chapter: Option<Area>,
+    pub side: AreaMode,
+    pub checkpoints: bool,
+    pub output: Option<PathBuf>,
+    pub force: bool,
+}
+
+/// Chapters of the full game categories, in the order they're played.
+const STORY: [Area; 8] = [
+    Area::Prologue,
+    Area::ForsakenCity,
+    Area::OldSite,
+    Area::CelestialResort,
+    Area::GoldenRidge,
+    Area::MirrorTemple,
+    Area::Reflection,
+    Area::TheSummit,
+];
+
+/// Red berries of the chapters of All Red Berries, as the chapter select shows them.
+const RED_BERRIES: [(Area, u32); 9] = [
+    (Area::Prologue, 0),
+    (Area::ForsakenCity, 20),
+    (Area::OldSite, 18),
+    (Area::CelestialResort, 25),
+    (Area::GoldenRidge, 29),
+    (Area::MirrorTemple, 31),
+    (Area::Reflection, 0),
+    (Area::TheSummit, 47),
+    (Area::Core, 5),
+];
+
+/// Chapters that have B and C-sides.
+const SIDED: [Area; 8] = [
+    Area::ForsakenCity,
+    Area::OldSite,
+    Area::CelestialResort,
+    Area::GoldenRidge,
+    Area::MirrorTemple,
+    Area::Reflection,
+    Area::TheSummit,
+    Area::Core,
+];
+
+fn side_name(side: AreaMode) -> &'static str {
+    match side {
+        AreaMode::BSide => "B-Side",
+        AreaMode::CSide => "C-Side",
+        _ => "A-Side",
+    }
+}
+
+/// The splits of a category, with the name of the segment each of them ends.
+struct Layout {
+    name: String,
+    segments: Vec<(String, Split)>,
+    auto_reset: bool,
+    /// Runs that don't start on a new file are timed from the file time they start at.
+    file_time_offset: bool,
+}
+
+/// Completing each chapter of `chapters`, on any side.
+fn chapters(chapters: &[Area], name: impl Fn(Area) -> String) -> Vec<(String, Split)> {
+    chapters
+        .iter()
+        .filter_map(|&area| Some((name(area), Split::chapter_complete(area)?)))
+        .collect()
+}
+
+fn layout(request: &Request) -> Result<Layout> {
+    let full_game = |name: &str, segments| Layout {
+        name: name.to_string(),
+        segments,
+        auto_reset: false,
+        file_time_offset: false,
+    };
+    let story_name = |area: Area| area.name().to_string();
+    Ok(match request.category {
+        Category::Any => full_game("Any%", chapters(&STORY, story_name)),
+        Category::TrueEnding => full_game(
+            "True Ending",
+            chapters(&[&STORY[..], &[Area::Core]].concat(), story_name),
+        ),
+        Category::AllRedBerries => {
+            // The file's berries are counted, so that it works without Everest too
+            let mut berries = 0;
+            let mut segments = Vec::new();
+            for (area, chapter_berries) in RED_BERRIES {
+                berries += chapter_berries;
+                // The chapters without red berries keep the usual split, which also makes the
+                // run a full game one
+                let split = match Split::chapter_complete(area) {
+                    Some(split) if chapter_berries == 0 => split,
+                    _ => Split::Expression {
+                        expression: format!(
+                            "area == {area:?} && chapter_complete && berries >= {berries}"
+                        )
+                        .parse()?,
+                    },
+                };
+                segments.push((story_name(area), split));
+            }
+            full_game("All Red Berries", segments)
+        }
+        Category::AllChapters => full_game(
+            "All Chapters",
+            chapters(
+                &[&STORY[..], &[Area::Core, Area::Farewell]].concat(),
+                story_name,
+            ),
+        ),
+        Category::OneHundred => {
+            let mut segments = chapters(&[Area::Prologue], story_name);
+            for area in SIDED {
+                for side in ["A", "B", "C"] {
+                    segments.extend(chapters(&[area], |area| format!("{} {side}", area.name())));
+                }
+                if area == Area::TheSummit {
+                    segments.extend(chapters(&[Area::Epilogue], story_name));
+                }
+            }
+            segments.extend(chapters(&[Area::Farewell], story_name));
+            full_game("100%", segments)
+        }
+        Category::AllASides => full_game(
+            "All A-Sides",
+            chapters(&[&STORY[..], &[Area::Core]].concat(), story_name),
+        ),
+        Category::AllBSides | Category::AllCSides => {
+            let side = if request.category == Category::AllBSides {
+                "B"
+            } else {
+                "C"
+            };
+            Layout {
+                name: format!("All {side}-Sides"),
+                segments: chapters(&SIDED, |area| format!("{} {side}", area.name())),
+                auto_reset: false,
+                file_time_offset: true,
+            }
+        }
+        Category::Il => {
+            let area = request.chapter.ok_or(anyhow!(
+                "give the chapter of the individual level with --chapter"
+            ))?;
+            let completion =
+                Split::chapter_complete(area).ok_or(anyhow!("{area:?} isn't a chapter"))?;
+            let side = request.side;
+            if !matches!(side, AreaMode::ASide | AreaMode::BSide | AreaMode::CSide) {
+                return Err(anyhow!("the side has to be ASide, BSide or CSide"));
+            }
+            if side != AreaMode::ASide && !SIDED.contains(&area) {
+                return Err(anyhow!("{area:?} has no {side:?}"));
+            }
+            let mut checkpoints: Vec<_> = CHECKPOINTS
+                .iter()
+                .filter(|checkpoint| checkpoint.area == area && checkpoint.mode == side)
+                .collect();
+            checkpoints.sort_by_key(|checkpoint| checkpoint.index);
+            let mut segments = Vec::new();
+            let mut segment = area.name().to_string();
+            if request.checkpoints && !checkpoints.is_empty() {
+                segment = String::from("Start");
+                for checkpoint in checkpoints {
+                    let split = Split::Checkpoint {
+                        area,
+                        mode: side,
+                        index: checkpoint.index,
+                    };
+                    segments.push((segment, split));
+                    segment = checkpoint.name.to_string();
+                }
+            }
+            segments.push((segment, completion));
+            let name = match area {
+                Area::Prologue | Area::Epilogue | Area::Farewell => area.name().to_string(),
+                _ => format!("{} {}", area.name(), side_name(side)),
+            };
+            Layout {
+                name,
+                segments,
+                auto_reset: true,
+                file_time_offset: false,
+            }
+        }
+    })
+}
+
+/// Escapes the text of an XML element.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn bool_text(value: bool) -> &'static str {
+    if value { "True" } else { "False" }
+}
+
+/// The splits file of `layout`, as written by LiveSplit.
+fn to_lss(layout: &Layout) -> Result<String> {
+    let mut lss = String::new();
+    writeln!(lss, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(lss, r#"<Run version="1.7.0">"#)?;
+    writeln!(lss, "  <GameIcon />")?;
+    writeln!(lss, "  <GameName>Celeste</GameName>")?;
+    writeln!(
+        lss,
+        "  <CategoryName>{}</CategoryName>",
+        escape(&layout.name)
+    )?;
+    writeln!(lss, "  <Offset>00:00:00</Offset>")?;
+    writeln!(lss, "  <AttemptCount>0</AttemptCount>")?;
+    writeln!(lss, "  <AttemptHistory />")?;
+    writeln!(lss, "  <Segments>")?;
+    for (name, _) in &layout.segments {
+        writeln!(lss, "    <Segment>")?;
+        writeln!(lss, "      <Name>{}</Name>", escape(name))?;
+        writeln!(lss, "      <Icon />")?;
+        writeln!(lss, "      <SplitTimes>")?;
+        writeln!(lss, r#"        <SplitTime name="Personal Best" />"#)?;
+        writeln!(lss, "      </SplitTimes>")?;
+        writeln!(lss, "      <BestSegmentTime />")?;
+        writeln!(lss, "      <SegmentHistory />")?;
+        writeln!(lss, "    </Segment>")?;
+    }
+    writeln!(lss, "  </Segments>")?;
+    writeln!(lss, "  <AutoSplitterSettings>")?;
+    writeln!(
+        lss,
+        "    <AutoReset>{}</AutoReset>",
+        bool_text(layout.auto_reset)
+    )?;
+    writeln!(lss, "    <SetGameTime>True</SetGameTime>")?;
+    writeln!(
+        lss,
+        "    <FileTimeOffset>{}</FileTimeOffset>",
+        bool_text(layout.file_time_offset)
+    )?;
+    writeln!(lss, "    <Splits>")?;
+    for (_, split) in &layout.segments {
+        writeln!(lss, "      <Split>{}</Split>", escape(&split.to_field()))?;
+    }
+    writeln!(lss, "    </Splits>")?;
+    writeln!(lss, "  </AutoSplitterSettings>")?;
+    writeln!(lss, "</Run>")?;
+    Ok(lss)
+}
+
+/// `linsplit generate`: writes a splits file for a standard category.
+pub fn run(request: &Request) -> Result<()> {
+    let layout = layout(request)?;
+    let path = match &request.output {
+        Some(path) => path.clone(),
+        None => PathBuf::from(format!("{}.lss", layout.name)),
+    };
+    if path.exists() && !request.force {
+        return Err(anyhow!(
+            "{} already exists, use --force to replace it",
+            path.display()
+        ));
+    }
+    fs::write(&path, to_lss(&layout)?)
+        .map_err(|e| anyhow!("couldn't write {}: {e}", path.display()))?;
+    check(&path, &layout)?;
+
+    println!(
+        "Wrote {} ({}, {} split{}).",
+        path.display(),
+        layout.name,
+        layout.segments.len(),
+        if layout.segments.len() == 1 { "" } else { "s" }
+    );
+    if layout.file_time_offset {
+        println!(
+            "This run doesn't start on a new file: start the timer yourself when entering the first chapter."
+        );
+    }
+    Ok(())
+}
+
+/// Reads the written file back, to make sure LinSplit understands every split of it.
+fn check(path: &Path, layout: &Layout) -> Result<()> {
+    let data = SplitData::read_splits(&path.to_string_lossy())?;
+    if data.splits.len() != layout.segments.len() {
+        return Err(anyhow!(
+            "only {} of the {} splits written to {} can be read back",
+            data.splits.len(),
+            layout.segments.len(),
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::split_reader::RunKind;
+    use clap::ValueEnum;
+
+    fn request(category: Category) -> Request {
+        Request {
+            category,
+            chapter: None,
+            side: AreaMode::ASide,
+            checkpoints: false,
+            output: None,
+            force: false,
+        }
+    }
+
+    /// Writes the splits of `request` and reads them back.
+    fn written(request: &Request, file_name: &str) -> (Layout, SplitData) {
+        let layout = layout(request).unwrap();
+        let path = std::env::temp_dir().join(format!(
+            "linsplit-generate-{}-{file_name}",
+            std::process::id()
+        ));
+        fs::write(&path, to_lss(&layout).unwrap()).unwrap();
+        let data = SplitData::read_splits(&path.to_string_lossy());
+        fs::remove_file(&path).unwrap();
+        (layout, data.unwrap())
+    }
+
+    #[test]
+    fn all_red_berries_splits_on_the_berries_of_each_chapter() {
+        let (layout, data) = written(&request(Category::AllRedBerries), "arb.lss");
+        assert_eq!(data.run_kind, RunKind::FullGame);
+        assert_eq!(data.splits.len(), RED_BERRIES.len());
+        assert_eq!(data.segment_names[8], "Core");
+        let fields: Vec<_> = data.splits.iter().map(Split::to_field).collect();
+        assert_eq!(fields[0], "Prologue");
+        assert_eq!(
+            fields[1],
+            "Expression,area == ForsakenCity && chapter_complete && berries >= 20"
+        );
+        assert_eq!(fields[6], "Chapter6");
+        assert_eq!(
+            fields[8],
+            "Expression,area == Core && chapter_complete && berries >= 175"
+        );
+        assert_eq!(layout.name, "All Red Berries");
+    }
+
+    /// Checks that the splits file of `request` reads back as the splits it was written from.
+    fn reads_back(request: &Request, file_name: &str) -> SplitData {
+        let (layout, data) = written(request, file_name);
+        let names: Vec<_> = layout.segments.iter().map(|(name, _)| name).collect();
+        let fields: Vec<_> = layout
+            .segments
+            .iter()
+            .map(|(_, split)| split.to_field())
+            .collect();
+        assert_eq!(data.segment_names.iter().collect::<Vec<_>>(), names);
+        assert_eq!(
+            data.splits.iter().map(Split::to_field).collect::<Vec<_>>(),
+            fields
+        );
+        assert_eq!(data.auto_reset, layout.auto_reset);
+        assert_eq!(data.file_time_offset, layout.file_time_offset);
+        data
+    }
+
+    #[test]
+    fn every_category_reads_back() {
+        for &category in Category::value_variants() {
+            if category == Category::Il {
+                continue;
+            }
+            let data = reads_back(&request(category), &format!("{category:?}.lss"));
+            assert_eq!(data.run_kind, RunKind::FullGame, "{category:?}");
+        }
+    }
+
+    #[test]
+    fn individual_levels_read_back() {
+        let mut request = request(Category::Il);
+        assert!(layout(&request).is_err());
+        request.chapter = Some(Area::OldSite);
+        request.side = AreaMode::BSide;
+        let data = reads_back(&request, "il.lss");
+        assert_eq!(data.run_kind, RunKind::IndividualLevel);
+        assert_eq!(data.splits.len(), 1);
+        request.checkpoints = true;
+        let data = reads_back(&request, "il-checkpoints.lss");
+        assert_eq!(
+            data.segment_names,
+            ["Start", "Combination Lock", "Dream Altar"]
+        );
+        request.chapter = Some(Area::Epilogue);
+        assert!(layout(&request).is_err());
+    }
+}
@@ -0,0 +1,334 @@
+use anyhow::{Result, anyhow};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::data_dir;
+use crate::memory_reader::game_data::GameState;
+
+const HISTORY_FILE: &str = "history.jsonl";
+
+/// A split of an attempt, as it happened.
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug)]
+pub struct SplitRecord {
+    pub name: String,
+    /// Game time of the split in seconds, `None` if it was skipped.
+    pub time: Option<f64>,
+    pub state: GameState,
+}
+
+/// An attempt of a run, stored as a line of the history file.
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug)]
+pub struct Attempt {
+    /// Seconds since the Unix epoch.
+    pub started: u64,
+    pub splits_file: String,
+    /// Names of the segments of the splits file.
+    pub segments: Vec<String>,
+    pub splits: Vec<SplitRecord>,
+    /// Index of the split the run was reset on, `None` if it was finished.
+    pub reset_at: Option<usize>,
+    pub final_time: Option<f64>,
+    /// Whether the game time went back during the attempt and it was kept as practice.
+    #[serde(default)]
+    pub practice: bool,
+}
+
+impl Attempt {
+    pub fn new(splits_file: &str, segments: &[String]) -> Self {
+        Attempt {
+            started: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs()),
+            splits_file: String::from(splits_file),
+            segments: segments.to_vec(),
+            splits: vec![],
+            reset_at: None,
+            final_time: None,
+            practice: false,
+        }
+    }
+
+    /// Name of the `index`th segment.
+    pub fn segment_name(&self, index: usize) -> String {
+        self.segments
+            .get(index)
+            .cloned()
+            .unwrap_or_else(|| format!("Split {}", index + 1))
+    }
+
+    /// Time of each segment, `None` for the skipped ones and the ones after them.
+    pub fn segment_times(&self) -> Vec<Option<f64>> {
+        let mut last = Some(0.);
+        self.splits
+            .iter()
+            .map(|split| {
+                let segment = last.zip(split.time).map(|(last, time)| time - last);
+                last = split.time;
+                segment
+            })
+            .collect()
+    }
+}
+
+fn history_path() -> Result<PathBuf> {
+    Ok(data_dir()?.join(HISTORY_FILE))
+}
+
+/// Appends an attempt to the history file.
+pub fn save(attempt: &Attempt) -> Result<()> {
+    let path = history_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| anyhow!("couldn't open {}: {e}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(attempt)?)?;
+    Ok(())
+}
+
+/// Reads every attempt of the history file, oldest first.
+pub fn load() -> Result<Vec<Attempt>> {
+    let path = history_path()?;
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(_) => return Ok(vec![]),
+    };
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| anyhow!("in {} line {}: {e}", path.display(), number + 1))
+        })
+        .collect()
+}
+
+/// Formats a time in seconds like LiveSplit does, `1:02:03.456` or `2:03.456`.
+pub fn format_time(seconds: f64) -> String {
+    let millis = (seconds.abs() * 1000.).round() as u64;
+    let sign = if seconds < 0. { "-" } else { "" };
+    let (hours, minutes, seconds, millis) = (
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000,
+    );
+    if hours > 0 {
+        format!("{sign}{hours}:{minutes:02}:{seconds:02}.{millis:03}")
+    } else {
+        format!("{sign}{minutes}:{seconds:02}.{millis:03}")
+    }
+}
+
+/// Formats seconds since the Unix epoch as a UTC date, `2024-01-31 12:34:56`.
+pub fn format_timestamp(timestamp: u64) -> String {
+    let (days, seconds) = (timestamp / 86400, timestamp % 86400);
+    // Days to civil date, from http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!(
+        "{year}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// `linsplit history list`
+pub fn list() -> Result<()> {
+    let attempts = load()?;
+    if attempts.is_empty() {
+        println!("No attempts recorded yet.");
+    }
+    for (id, attempt) in attempts.iter().enumerate() {
+        let result = match (attempt.reset_at, attempt.final_time) {
+            (None, Some(time)) => format!("finished in {}", format_time(time)),
+            (Some(index), _) => format!("reset at {}", attempt.segment_name(index)),
+            _ => String::from("unknown"),
+        };
+        let practice = if attempt.practice { " (practice)" } else { "" };
+        println!(
+            "{:>4}  {}  {}  {result}{practice}",
+            id + 1,
+            format_timestamp(attempt.started),
+            attempt.splits_file
+        );
+    }
+    Ok(())
+}
+
+/// `linsplit history show <ID>`
+pub fn show(id: usize) -> Result<()> {
+    let attempts = load()?;
+    let attempt = id
+        .checked_sub(1)
+        .and_then(|index| attempts.get(index))
+        .ok_or(anyhow!("no attempt with the id {id}"))?;
+    println!(
+        "Attempt {id}, started {}",
+        format_timestamp(attempt.started)
+    );
+    println!("Splits: {}", attempt.splits_file);
+    if attempt.practice {
+        println!("Practice: the game time went back during the attempt");
+    }
+    for (split, segment) in attempt.splits.iter().zip(attempt.segment_times()) {
+        println!(
+            "  {:<24} {:>12} {:>12}  {:?} {:?} {}",
+            split.name,
+            split.time.map_or(String::from("-"), format_time),
+            segment.map_or(String::from("-"), format_time),
+            split.state.area_id,
+            split.state.area_difficulty,
+            split.state.level_name
+        );
+    }
+    match (attempt.reset_at, attempt.final_time) {
+        (None, Some(time)) => println!("Finished in {}", format_time(time)),
+        (Some(index), _) => println!("Reset at {}", attempt.segment_name(index)),
+        _ => {}
+    }
+    Ok(())
+}
+
+/// The best segment of each split, `None` if it was never reached, and how many attempts
+/// were reset on it.
+fn split_stats(attempts: &[&Attempt]) -> (Vec<Option<f64>>, Vec<usize>) {
+    let count = attempts
+        .iter()
+        .map(|attempt| {
+            attempt
+                .segments
+                .len()
+                .max(attempt.splits.len())
+                .max(attempt.reset_at.map_or(0, |index| index + 1))
+        })
+        .max()
+        .unwrap_or(0);
+    let mut bests: Vec<Option<f64>> = vec![None; count];
+    let mut resets: Vec<usize> = vec![0; count];
+    for attempt in attempts {
+        for (index, segment) in attempt.segment_times().into_iter().enumerate() {
+            if let Some(segment) = segment {
+                bests[index] = Some(bests[index].map_or(segment, |best| best.min(segment)));
+            }
+        }
+        if let Some(index) = attempt.reset_at {
+            resets[index] += 1;
+        }
+    }
+    (bests, resets)
+}
+
+/// `linsplit history stats [SPLITS]`: best segments, sum of best and resets per split, for
+/// the attempts of a splits file (the one of the last attempt by default).
+pub fn stats(splits_file: Option<&str>) -> Result<()> {
+    let attempts = load()?;
+    let Some(splits_file) = splits_file.or(attempts.last().map(|a| a.splits_file.as_str())) else {
+        println!("No attempts recorded yet.");
+        return Ok(());
+    };
+    let (practice, attempts): (Vec<&Attempt>, Vec<&Attempt>) = attempts
+        .iter()
+        .filter(|attempt| attempt.splits_file == splits_file)
+        .partition(|attempt| attempt.practice);
+    let Some(last) = attempts.last() else {
+        println!("No attempts recorded for {splits_file}.");
+        return Ok(());
+    };
+    let finished = attempts.iter().filter(|a| a.reset_at.is_none()).count();
+    let practice = match practice.len() {
+        0 => String::new(),
+        count => format!(" ({count} practice attempts left out)"),
+    };
+    println!(
+        "{splits_file}: {} attempts, {finished} finished{practice}",
+        attempts.len()
+    );
+
+    let (bests, resets) = split_stats(&attempts);
+    println!("  {:<24} {:>12} {:>8}", "Split", "Best", "Resets");
+    for (index, (best, resets)) in bests.iter().zip(&resets).enumerate() {
+        println!(
+            "  {:<24} {:>12} {resets:>8}",
+            last.segment_name(index),
+            best.map_or(String::from("-"), format_time)
+        );
+    }
+    if !bests.is_empty() && bests.iter().all(Option::is_some) {
+        println!(
+            "Sum of best: {}",
+            format_time(bests.iter().flatten().sum::<f64>())
+        );
+    } else {
+        println!("Sum of best: incomplete, some splits were never reached");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attempt(times: &[Option<f64>], reset_at: Option<usize>) -> Attempt {
+        let mut attempt = Attempt::new("/splits/any.lss", &[String::from("City")]);
+        attempt.splits = times
+            .iter()
+            .map(|&time| SplitRecord {
+                name: String::new(),
+                time,
+                state: GameState::default(),
+            })
+            .collect();
+        attempt.reset_at = reset_at;
+        attempt
+    }
+
+    #[test]
+    fn segments_after_a_skipped_split_have_no_time() {
+        let attempt = attempt(&[Some(10.), None, Some(30.), Some(35.5)], None);
+        assert_eq!(attempt.segment_times(), [Some(10.), None, None, Some(5.5)]);
+    }
+
+    #[test]
+    fn best_segments_and_resets() {
+        let attempts = [
+            attempt(&[Some(10.), Some(25.), Some(40.)], None),
+            attempt(&[Some(12.), Some(20.)], Some(2)),
+            attempt(&[Some(9.)], Some(1)),
+            attempt(&[], Some(1)),
+        ];
+        let attempts: Vec<&Attempt> = attempts.iter().collect();
+        let (bests, resets) = split_stats(&attempts);
+        assert_eq!(bests, [Some(9.), Some(8.), Some(15.)]);
+        assert_eq!(resets, [0, 2, 1]);
+    }
+
+    #[test]
+    fn segment_names_default_to_their_number() {
+        let attempt = attempt(&[], None);
+        assert_eq!(attempt.segment_name(0), "City");
+        assert_eq!(attempt.segment_name(2), "Split 3");
+    }
+
+    #[test]
+    fn formats_times_like_livesplit() {
+        assert_eq!(format_time(0.), "0:00.000");
+        assert_eq!(format_time(83.4567), "1:23.457");
+        assert_eq!(format_time(3723.004), "1:02:03.004");
+        assert_eq!(format_time(-1.5), "-0:01.500");
+        assert_eq!(format_timestamp(1_706_704_496), "2024-01-31 12:34:56");
+    }
+}
@@ -0,0 +1,117 @@
+use anyhow::{Result, anyhow};
+use log::info;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+
+use crate::linsplit_data::LinSplitData;
+use crate::memory_reader::game_data::GameState;
+
+/// Requests bigger than this are refused.
+const MAX_REQUEST_SIZE: usize = 8192;
+
+/// Answer of `GET /status`.
+#[derive(serde_derive::Serialize, Debug)]
+pub struct Status {
+    pub state: GameState,
+    /// Index of the current split, -1 when the timer isn't running.
+    pub split_index: i32,
+    pub split: Option<String>,
+    pub segment: Option<String>,
+    pub timer_connected: bool,
+}
+
+/// Serves `GET /status`, the current state as JSON, and `GET /events`, the event stream as
+/// server-sent events.
+pub async fn serve(address: &str, data: Arc<LinSplitData>) -> Result<()> {
+    let listener = TcpListener::bind(address)
+        .await
+        .map_err(|e| anyhow!("couldn't listen on {address}: {e}"))?;
+    info!("Serving the game's state on http://{address}/status");
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let data = Arc::clone(&data);
+            tokio::spawn(async move {
+                let _ = handle(stream, data).await;
+            });
+        }
+    });
+    Ok(())
+}
+
+async fn handle(mut stream: TcpStream, data: Arc<LinSplitData>) -> Result<()> {
+    let mut request = vec![];
+    let mut buffer = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        request.extend_from_slice(&buffer[..read]);
+        if request.len() > MAX_REQUEST_SIZE {
+            return respond(&mut stream, "413 Payload Too Large", "text/plain", "").await;
+        }
+    }
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+    let (method, path) = (request_line.next(), request_line.next());
+    // Ignore the query string
+    let path = path.map(|path| path.split('?').next().unwrap_or_default());
+    match (method, path) {
+        (Some("GET"), Some("/status")) => {
+            let status = serde_json::to_string(&data.status().await)?;
+            respond(&mut stream, "200 OK", "application/json", &status).await
+        }
+        (Some("GET"), Some("/events")) => match data.subscribe_events() {
+            Some(receiver) => stream_events(stream, receiver).await,
+            None => respond(&mut stream, "404 Not Found", "text/plain", "").await,
+        },
+        (Some("GET"), _) => respond(&mut stream, "404 Not Found", "text/plain", "").await,
+        _ => respond(&mut stream, "405 Method Not Allowed", "text/plain", "").await,
+    }
+}
+
+async fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> Result<()> {
+    let response = format!(
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: {content_type}\r\n\
+         Content-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+async fn stream_events(
+    mut stream: TcpStream,
+    mut receiver: broadcast::Receiver<String>,
+) -> Result<()> {
+    stream
+        .write_all(
+            b"HTTP/1.1 200 OK\r\n\
+              Content-Type: text/event-stream\r\n\
+              Cache-Control: no-cache\r\n\
+              Access-Control-Allow-Origin: *\r\n\
+              Connection: keep-alive\r\n\r\n",
+        )
+        .await?;
+    loop {
+        match receiver.recv().await {
+            Ok(line) => {
+                stream
+                    .write_all(format!("data: {line}\n\n").as_bytes())
+                    .await?
+            }
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => return Ok(()),
+        }
+    }
+}
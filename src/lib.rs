@@ -0,0 +1,5 @@
+#![forbid(unsafe_code)]
+//! Reading Celeste's state, shared by `linsplit` and `linsplit-helper`.
+pub mod area;
+pub mod memory_reader;
+pub mod save_reader;
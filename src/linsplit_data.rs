@@ -1,18 +1,31 @@
 use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
+use log::{debug, error, info, warn};
 use tokio::sync::{Mutex, Notify, RwLock};
 
+use crate::config::{Options, TimeRegressionPolicy, TimerBackend};
+use crate::event_stream::{EventStream, StreamEvent};
+use crate::history::{self, Attempt, SplitRecord};
+use crate::http_server::{self, Status};
 use crate::livesplitone::SplitterSocket;
 use crate::livesplitone::commands::{Command, Event, TimeSpan, TimingMethod};
-use crate::memory_reader::game_data::GameData;
-use crate::split_reader::{Area, AreaMode, Split, SplitData};
+use crate::memory_reader::game_data::{GameData, GameState};
+use crate::memory_reader::helper_reader::HelperCommand;
+use crate::obs_output::ObsOutput;
+use crate::room_times::RoomTimes;
+use crate::split_reader::{Area, AreaMode, RunKind, Split, SplitData, checkpoints};
+use crate::split_trace::{self, Conditions, SplitTracer};
+use crate::time_regression::TimeWatch;
+use crate::timer::{SimulatedTimer, Timer};
 
 pub struct LinSplitData {
     splits: SplitData,
-    socket: Arc<SplitterSocket>,
+    timer: Timer,
     game_data: RwLock<GameData>,
     events: Arc<Mutex<VecDeque<Event>>>,
     event_notifications: Arc<Notify>,
@@ -20,65 +33,330 @@ pub struct LinSplitData {
     current_split: Mutex<i32>,
     last_area_id: Mutex<Area>,
     last_area_difficulty: Mutex<AreaMode>,
+    forbid_assists: bool,
+    time_regression: TimeRegressionPolicy,
+    polling_interval: Duration,
+    room_times_path: Option<PathBuf>,
+    /// Canonical path of the splits file, for the history.
+    splits_path: String,
+    /// Attempt being recorded in the history.
+    attempt: Mutex<Option<Attempt>>,
+    /// Last game time sent to the timer, in seconds.
+    game_time: Mutex<f64>,
+    /// Game time and state of the split sent to the timer, until the timer confirms it.
+    split_sent: Mutex<Option<(f64, GameState)>>,
+    event_stream: Option<EventStream>,
+    obs_output: Option<Mutex<ObsOutput>>,
+    /// Whether the run is only simulated: nothing is written to the history or to the
+    /// outputs, and the timer events are handled between the updates.
+    dry_run: bool,
 }
 
+/// How long to wait for LiveSplit One and the event consumers when stopping.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Custom variable set in LiveSplit One when a run can't be valid.
+const INVALID_RUN_VARIABLE: &str = "Invalid Run";
+
+/// Custom variable set in LiveSplit One when the game time went back during a run.
+const PRACTICE_VARIABLE: &str = "Practice Attempt";
+
 impl LinSplitData {
-    pub async fn new(file_path: &str, addr: &str, save_location: String) -> Arc<Self> {
+    pub async fn new(options: &Options) -> anyhow::Result<Arc<Self>> {
         let events = Arc::new(Mutex::new(VecDeque::new()));
         let event_notifications = Arc::new(Notify::new());
-        let splits = SplitData::read_splits(file_path).unwrap();
-        let socket =
-            SplitterSocket::new(addr, Arc::clone(&events), Arc::clone(&event_notifications))
-                .await
-                .unwrap();
-        let game_data = RwLock::new(GameData::new(save_location).await);
+        let splits = Self::read_splits(options)?;
+        let timer = match options.timer {
+            TimerBackend::LiveSplitOne => Timer::LiveSplitOne(
+                SplitterSocket::new(
+                    &options.address,
+                    Arc::clone(&events),
+                    Arc::clone(&event_notifications),
+                )
+                .await?,
+            ),
+        };
+        let helper = options.helper.as_ref().map(|path| HelperCommand {
+            path: path.clone(),
+            save_location: options.save_location.clone(),
+            polling_interval: options.polling_interval,
+        });
+        let game_data = GameData::new(options.save_location.clone(), helper).await;
         // tokio::time::sleep(Duration::from_secs(3)).await;
-        let data = Arc::new(LinSplitData {
+        let data = Self::with(
+            options,
             splits,
-            socket,
+            timer,
             game_data,
+            events,
+            event_notifications,
+            false,
+        )?;
+        let data_loop = Arc::clone(&data);
+        tokio::spawn(async move { data_loop.event_loop().await });
+        if let Some(address) = &options.http {
+            http_server::serve(address, Arc::clone(&data)).await?;
+        }
+
+        Ok(data)
+    }
+
+    /// Runs the splits against the states recorded in `trace` instead of the game, with a
+    /// timer that only records what it's asked to do.
+    pub fn simulation(
+        options: &Options,
+        trace: &Path,
+    ) -> anyhow::Result<(Arc<Self>, Arc<SimulatedTimer>)> {
+        let events = Arc::new(Mutex::new(VecDeque::new()));
+        let splits = Self::read_splits(options)?;
+        let timer = Arc::new(SimulatedTimer::new(
+            splits.segment_names.clone(),
+            Arc::clone(&events),
+        ));
+        let game_data = GameData::replay(trace)?;
+        let data = Self::with(
+            options,
+            splits,
+            Timer::Simulated(Arc::clone(&timer)),
+            game_data,
+            events,
+            Arc::new(Notify::new()),
+            true,
+        )?;
+        Ok((data, timer))
+    }
+
+    fn read_splits(options: &Options) -> anyhow::Result<SplitData> {
+        let mut splits = SplitData::read_splits(&options.splits)?;
+        splits.apply_overrides(&options.overrides);
+        Ok(splits)
+    }
+
+    fn with(
+        options: &Options,
+        splits: SplitData,
+        timer: Timer,
+        game_data: GameData,
+        events: Arc<Mutex<VecDeque<Event>>>,
+        event_notifications: Arc<Notify>,
+        dry_run: bool,
+    ) -> anyhow::Result<Arc<Self>> {
+        let event_stream = if !dry_run && (options.events.is_some() || options.http.is_some()) {
+            let event_stream = EventStream::new();
+            if let Some(output) = &options.events {
+                event_stream.write_to(output)?;
+            }
+            Some(event_stream)
+        } else {
+            None
+        };
+        let obs_output = match &options.obs_dir {
+            Some(dir) if !dry_run => Some(Mutex::new(ObsOutput::new(
+                dir.clone(),
+                &options.obs_formats,
+            )?)),
+            _ => None,
+        };
+        Ok(Arc::new(LinSplitData {
+            splits,
+            timer,
+            game_data: RwLock::new(game_data),
             exiting_chapter: Mutex::new(false),
             events,
             event_notifications,
             current_split: Mutex::new(-1),
             last_area_id: Mutex::new(Area::Unknown),
             last_area_difficulty: Mutex::new(AreaMode::Unknown),
-        });
-        let data_loop = Arc::clone(&data);
-        tokio::spawn(async move { data_loop.event_loop().await });
+            forbid_assists: options.forbid_assists,
+            time_regression: options.time_regression,
+            polling_interval: options.polling_interval,
+            room_times_path: options.room_times.clone(),
+            splits_path: fs::canonicalize(&options.splits).map_or_else(
+                |_| options.splits.clone(),
+                |path| path.display().to_string(),
+            ),
+            attempt: Mutex::new(None),
+            game_time: Mutex::new(0.),
+            split_sent: Mutex::new(None),
+            event_stream,
+            obs_output,
+            dry_run,
+        }))
+    }
 
-        data
+    /// Current state of the game and of the run.
+    pub async fn status(&self) -> Status {
+        let split_index = *self.current_split.lock().await;
+        let add_amount = self.splits.run_kind.starts_on_first_split() as i32;
+        let split = usize::try_from(split_index + add_amount)
+            .ok()
+            .and_then(|index| self.splits.splits.get(index));
+        Status {
+            state: self.game_data.read().await.state.clone(),
+            split_index,
+            split: split.map(|split| format!("{split:?}")),
+            segment: usize::try_from(split_index)
+                .ok()
+                .and_then(|index| self.splits.segment_names.get(index))
+                .cloned(),
+            timer_connected: self.timer.is_connected(),
+        }
+    }
+
+    /// The events emitted from now on, if the event stream is enabled.
+    pub fn subscribe_events(&self) -> Option<tokio::sync::broadcast::Receiver<String>> {
+        self.event_stream.as_ref().map(EventStream::subscribe)
+    }
+
+    /// Leaves the timer, the history and the event stream in a clean state, once the main
+    /// loop stopped.
+    pub async fn shutdown(&self) {
+        let index = *self.current_split.lock().await;
+        if index >= 0 {
+            self.save_attempt(Some(index as usize)).await;
+        }
+        if self.timer.is_connected() {
+            // Otherwise LiveSplit One keeps the game time running without the game
+            if self.splits.set_game_time && index >= 0 {
+                let pause = self.timer.send_command(Command::PauseGameTime);
+                match tokio::time::timeout(SHUTDOWN_TIMEOUT, pause).await {
+                    Ok(Ok(_)) => {}
+                    Ok(Err(error)) => warn!("Couldn't pause the game time: {error}"),
+                    Err(_) => warn!("LiveSplitOne didn't answer, the game time isn't paused"),
+                }
+            }
+            match tokio::time::timeout(SHUTDOWN_TIMEOUT, self.timer.close()).await {
+                Ok(Ok(())) => {}
+                Ok(Err(error)) => {
+                    warn!("Couldn't close the connection to LiveSplitOne: {error}")
+                }
+                Err(_) => warn!("Couldn't close the connection to LiveSplitOne in time"),
+            }
+        }
+        if let Some(event_stream) = &self.event_stream {
+            let _ = tokio::time::timeout(SHUTDOWN_TIMEOUT, event_stream.flush()).await;
+        }
     }
 
     async fn event_loop(self: Arc<Self>) {
         loop {
             self.event_notifications.notified().await;
             if let Some(event) = self.events.lock().await.pop_front() {
-                match event {
-                    Event::Started => {
-                        *self.current_split.lock().await = 0;
-                    }
-                    Event::Splitted | Event::Finished => {
-                        *self.current_split.lock().await += 1;
-                        *self.exiting_chapter.lock().await = false;
-                    }
-                    Event::Reset => {
-                        *self.current_split.lock().await = -1;
-                        *self.exiting_chapter.lock().await = false;
-                        *self.last_area_id.lock().await = Area::Unknown;
-                        *self.last_area_difficulty.lock().await = AreaMode::Unknown;
-                    }
-                    Event::SplitUndone => {
-                        *self.current_split.lock().await -= 1;
-                        *self.exiting_chapter.lock().await = false;
-                    }
-                    Event::SplitSkipped => {
-                        *self.current_split.lock().await += 1;
-                        *self.exiting_chapter.lock().await = false;
-                    }
-                    _ => {}
+                self.handle_event(event).await;
+            }
+        }
+    }
+
+    /// Handles the events sent by the simulated timer, which doesn't notify the event loop.
+    async fn handle_pending_events(&self) {
+        loop {
+            let event = self.events.lock().await.pop_front();
+            match event {
+                Some(event) => self.handle_event(event).await,
+                None => return,
+            }
+        }
+    }
+
+    /// Follows the state of the timer.
+    async fn handle_event(&self, event: Event) {
+        self.emit(StreamEvent::Timer {
+            event: format!("{event:?}"),
+        });
+        match event {
+            Event::Started => {
+                *self.current_split.lock().await = 0;
+                *self.split_sent.lock().await = None;
+                *self.attempt.lock().await =
+                    Some(Attempt::new(&self.splits_path, &self.splits.segment_names));
+            }
+            Event::Splitted | Event::Finished => {
+                let index = *self.current_split.lock().await;
+                self.record_split(index, true).await;
+                if matches!(event, Event::Finished) {
+                    self.save_attempt(None).await;
                 }
+                *self.current_split.lock().await += 1;
+                *self.exiting_chapter.lock().await = false;
             }
+            Event::Reset => {
+                let index = *self.current_split.lock().await;
+                if index >= 0 {
+                    self.save_attempt(Some(index as usize)).await;
+                }
+                *self.current_split.lock().await = -1;
+                *self.split_sent.lock().await = None;
+                *self.exiting_chapter.lock().await = false;
+                *self.last_area_id.lock().await = Area::Unknown;
+                *self.last_area_difficulty.lock().await = AreaMode::Unknown;
+            }
+            Event::SplitUndone => {
+                if let Some(attempt) = self.attempt.lock().await.as_mut() {
+                    attempt.splits.pop();
+                }
+                *self.current_split.lock().await -= 1;
+                *self.exiting_chapter.lock().await = false;
+            }
+            Event::SplitSkipped => {
+                let index = *self.current_split.lock().await;
+                self.record_split(index, false).await;
+                *self.current_split.lock().await += 1;
+                *self.exiting_chapter.lock().await = false;
+            }
+            _ => {}
+        }
+    }
+
+    /// Waits before reading the game again. Traces are replayed as fast as they can be.
+    async fn wait_for_next_update(&self) {
+        if self.dry_run {
+            tokio::task::yield_now().await;
+        } else {
+            tokio::time::sleep(self.polling_interval).await;
+        }
+    }
+
+    fn emit(&self, event: StreamEvent) {
+        if let Some(event_stream) = &self.event_stream {
+            event_stream.emit(event);
+        }
+    }
+
+    /// Adds the split `index` to the attempt being recorded, with the game time if it wasn't
+    /// skipped. That's the time of the state that made LinSplit split, or the current one for
+    /// the splits made from the timer.
+    async fn record_split(&self, index: i32, timed: bool) {
+        let (time, state) = match self.split_sent.lock().await.take() {
+            Some(sent) => sent,
+            None => (
+                *self.game_time.lock().await,
+                self.game_data.read().await.state.clone(),
+            ),
+        };
+        if let Some(attempt) = self.attempt.lock().await.as_mut() {
+            attempt.splits.push(SplitRecord {
+                name: attempt.segment_name(index.max(0) as usize),
+                time: timed.then_some(time),
+                state,
+            });
+        }
+    }
+
+    /// Ends the attempt being recorded and appends it to the history, as reset on the split
+    /// `reset_at` or finished.
+    async fn save_attempt(&self, reset_at: Option<usize>) {
+        let Some(mut attempt) = self.attempt.lock().await.take() else {
+            return;
+        };
+        if self.dry_run {
+            return;
+        }
+        attempt.reset_at = reset_at;
+        if reset_at.is_none() {
+            attempt.final_time = attempt.splits.last().and_then(|split| split.time);
+        }
+        if let Err(error) = history::save(&attempt) {
+            error!("Couldn't save the attempt to the history: {error}");
         }
     }
 
@@ -89,22 +367,37 @@ impl LinSplitData {
         level: &str,
         completed: bool,
         last_completed: bool,
+        conditions: &mut Conditions,
     ) -> bool {
         let mut exiting_chapter = self.exiting_chapter.lock().await;
         if !*exiting_chapter {
-            let level_name = if chapter_area == Area::TheSummit {
-                Some(level)
-            } else {
-                None
-            };
-            *exiting_chapter = area_id == chapter_area
-                && completed
-                && !last_completed
-                && (chapter_area != Area::TheSummit
-                    || (level_name
-                        .is_some_and(|name| !name.to_lowercase().starts_with("credits"))));
-            return *exiting_chapter && self.splits.il_splits;
+            let in_chapter = conditions.expect("area", area_id, chapter_area);
+            conditions.add("completed", completed);
+            conditions.add("last completed", last_completed);
+            conditions.add("exiting chapter", false);
+            let chapter_timed = self.splits.run_kind.is_chapter_timed();
+            conditions.add(
+                "split",
+                if chapter_timed {
+                    "when completing the chapter"
+                } else {
+                    "when leaving the completed chapter"
+                },
+            );
+            let credits =
+                chapter_area == Area::TheSummit && level.to_lowercase().starts_with("credits");
+            if chapter_area == Area::TheSummit {
+                conditions.add(
+                    "room",
+                    format!("{level} (the credits don't count: {credits})"),
+                );
+            }
+            *exiting_chapter = in_chapter && completed && !last_completed && !credits;
+            return *exiting_chapter && chapter_timed;
         }
+        conditions.add("completed", completed);
+        conditions.add("last completed", last_completed);
+        conditions.add("exiting chapter", true);
         !completed && last_completed
     }
 
@@ -115,31 +408,27 @@ impl LinSplitData {
         level: &str,
         completed: bool,
         last_completed: bool,
+        conditions: &mut Conditions,
     ) -> bool {
-        let split_info: Vec<&str> = area.split("-").collect();
-        match split_info[..] {
-            [chapter] => {
-                if let Ok(split_area) = Area::from_str(chapter.trim()) {
-                    return self
-                        .chapter_split(area_id, split_area, level, completed, last_completed)
-                        .await;
-                }
-
-                false
-            }
-            [chapter, difficulty] => {
-                if let Ok(split_area) = Area::from_str(chapter.trim())
-                    && let Ok(area_difficulty) = AreaMode::from_str(difficulty.trim())
-                {
-                    return self
-                        .chapter_split(area_id, split_area, level, completed, last_completed)
-                        .await
-                        && area_difficulty == *self.last_area_difficulty.lock().await;
-                }
-
-                false
+        let Some((split_area, area_difficulty)) = area_argument(area, conditions) else {
+            return false;
+        };
+        let split = self
+            .chapter_split(
+                area_id,
+                split_area,
+                level,
+                completed,
+                last_completed,
+                conditions,
+            )
+            .await;
+        match area_difficulty {
+            Some(area_difficulty) => {
+                let last_area_difficulty = *self.last_area_difficulty.lock().await;
+                conditions.expect("side", last_area_difficulty, area_difficulty) && split
             }
-            _ => false,
+            None => split,
         }
     }
 
@@ -150,33 +439,59 @@ impl LinSplitData {
         area_id_to_check: Area,
         curr_area_difficulty: AreaMode,
         area_difficulty_to_check: AreaMode,
+        conditions: &mut Conditions,
     ) -> bool {
-        let split_info: Vec<&str> = area.split("-").collect();
-        match split_info[..] {
-            [chapter] => {
-                if let Ok(split_area_id) = Area::from_str(chapter.trim()) {
-                    return curr_area_id != *self.last_area_id.lock().await
-                        && area_id_to_check == split_area_id;
-                }
-            }
-            [chapter, difficulty] => {
-                if let Ok(split_area) = Area::from_str(chapter.trim())
-                    && let Ok(area_difficulty) = AreaMode::from_str(difficulty.trim())
-                {
-                    return curr_area_id != *self.last_area_id.lock().await
-                        && area_id_to_check == split_area
-                        && curr_area_difficulty != *self.last_area_difficulty.lock().await
-                        && area_difficulty_to_check == area_difficulty;
-                }
+        let Some((split_area, area_difficulty)) = area_argument(area, conditions) else {
+            return false;
+        };
+        let last_area_id = *self.last_area_id.lock().await;
+        let area_changed = conditions.changed("area", curr_area_id, last_area_id);
+        let right_area = conditions.expect("area to check", area_id_to_check, split_area);
+        match area_difficulty {
+            Some(area_difficulty) => {
+                let last_area_difficulty = *self.last_area_difficulty.lock().await;
+                let side_changed =
+                    conditions.changed("side", curr_area_difficulty, last_area_difficulty);
+                let right_side =
+                    conditions.expect("side to check", area_difficulty_to_check, area_difficulty);
+                area_changed && right_area && side_changed && right_side
             }
-            _ => {}
+            None => area_changed && right_area,
+        }
+    }
+
+    /// Splits on collecting the cassette or the crystal heart of `area`, or of any chapter if
+    /// it's not given.
+    fn collectible_split(
+        &self,
+        state: &GameState,
+        area: Option<Area>,
+        cassette: bool,
+        last_count: u32,
+        conditions: &mut Conditions,
+    ) -> bool {
+        let right_area = area.is_none_or(|area| conditions.expect("area", state.area_id, area));
+        let (name, collected, count) = if cassette {
+            (
+                "cassettes",
+                state.chapter_cassette_collected,
+                state.cassettes,
+            )
+        } else {
+            ("hearts", state.chapter_heart_collected, state.heart_gems)
         };
-        false
+        let chapter_collectibles =
+            self.splits.run_kind.is_chapter_timed() || self.splits.file_time_offset;
+        if chapter_collectibles {
+            conditions.add("collected in the chapter", collected);
+        }
+        conditions.add(name, format!("{count}, was {last_count}"));
+        right_area && ((chapter_collectibles && collected) || count == last_count + 1)
     }
 
     #[rustfmt::skip]
-    pub async fn main_loop(&self) {
-        let elapsed_offset = self.game_data.read().await.game_time;
+    pub async fn main_loop(&self) -> anyhow::Result<()> {
+        let elapsed_offset = self.game_data.read().await.state.game_time;
         let mut last_level_name: String = Default::default();
         let mut _level_started: String = Default::default(); // Might be useful for later, cf https://github.com/ShootMe/LiveSplit.Celeste/blob/5c5bcb2c1456ee04a241575608febb9d35f69084/SplitterComponent.cs#L185
         let mut level_timer: f64 = 0.;
@@ -186,76 +501,172 @@ impl LinSplitData {
         let mut last_cassettes = 10000;
         let mut last_heart_gems = 10000;
         let mut last_area_difficulty = AreaMode::Unknown;
+        let mut last_forbidden_modes: Vec<&str> = vec![];
+        let mut last_state: Option<GameState> = None;
+        let mut last_split_index = None;
+        let mut time_watch = TimeWatch::default();
+        let mut marked_practice = false;
+        let mut marked_invalid = false;
+        // Whether the timer was asked to reset the run and hasn't done it yet
+        let mut reset_requested = false;
+        let mut tracer = SplitTracer::default();
+        let mut room_times = if self.splits.run_kind == RunKind::PerRoom && !self.dry_run {
+            match RoomTimes::new(self.room_times_path.clone()) {
+                Ok(room_times) => Some(room_times),
+                Err(error) => {
+                    warn!("Couldn't load the room times, they won't be recorded: {error}");
+                    None
+                }
+            }
+        } else {
+            None
+        };
         if self.splits.set_game_time {
-            self.socket
+            self.timer
                 .send_command(Command::SetCurrentTimingMethod {
                     timing_method: TimingMethod::GameTime,
                 })
-                .await
-                .unwrap();
+                .await?;
         }
         loop {
-            self.game_data.write().await.update();
-            let mut should_split = false;
-            if *self.current_split.lock().await == -1 && (self.splits.splits.is_empty() || self.splits.chapter_splits)
             {
-                if self.splits.splits.is_empty() {
-                    let level_name = self.game_data.read().await.level_name.clone();
+                let mut game_data = self.game_data.write().await;
+                if let Err(error) = game_data.update() {
+                    if game_data.ended() {
+                        return Ok(());
+                    }
+                    if error.consecutive_failures == 1 {
+                        warn!("Couldn't read the game's state: {error}");
+                    }
+                    if game_data.should_reattach() {
+                        warn!("Lost Celeste, searching for it again.");
+                        // Searching can take as long as the game is closed, the last state stays readable meanwhile
+                        let search = game_data.search_again();
+                        drop(game_data);
+                        let reader = search.await;
+                        self.game_data.write().await.attach(reader);
+                        continue;
+                    }
+                    drop(game_data);
+                    self.wait_for_next_update().await;
+                    continue;
+                }
+            }
+            if self.dry_run {
+                self.handle_pending_events().await;
+            }
+            if let Some(event_stream) = &self.event_stream {
+                event_stream.emit_state(&self.game_data.read().await.state);
+            }
+            if let Some(obs_output) = &self.obs_output {
+                let index = *self.current_split.lock().await;
+                let segment = usize::try_from(index).ok().and_then(|index| self.splits.segment_names.get(index));
+                let time = *self.game_time.lock().await;
+                if let Err(error) = obs_output.lock().await.update(&self.game_data.read().await.state, segment.map(String::as_str), time) {
+                    warn!("Couldn't write the OBS files: {error}");
+                }
+            }
+            if let Some(room_times) = room_times.as_mut() {
+                match room_times.update(&self.game_data.read().await.state) {
+                    Ok(Some(room)) => info!("{}: {:.3} ({:+.3})", room.room, room.time, room.time - room.best),
+                    Ok(None) => {}
+                    Err(error) => warn!("Couldn't save the room times: {error}"),
+                }
+            }
+            let mut should_split = false;
+            let mut time_went_back = false;
+            // What made the split fire, for the simulations
+            let mut reason = None;
+            let run_kind = self.splits.run_kind;
+            let tracing = SplitTracer::enabled() || self.dry_run;
+            if *self.current_split.lock().await == -1 && !run_kind.starts_on_first_split() {
+                let mut conditions = Conditions::new(tracing);
+                match run_kind {
+                    RunKind::PerRoom => {
+                        let level_name = self.game_data.read().await.state.level_name.clone();
 
-                    should_split =
-                        !level_name.is_empty() && !last_level_name.is_empty() && *level_name != last_level_name;
+                        let room_changed = conditions.changed("room", level_name.as_str(), last_level_name.as_str());
+                        should_split =
+                            !level_name.is_empty() && !last_level_name.is_empty() && room_changed;
 
-                    if should_split {
-                        _level_started = last_level_name.clone();
-                        level_timer = self.game_data.read().await.level_time;
+                        if should_split {
+                            _level_started = last_level_name.clone();
+                            level_timer = self.game_data.read().await.state.level_time;
+                        }
+                        last_level_name = level_name.clone();
                     }
-                    last_level_name = level_name.clone();
-                } else if !self.splits.il_splits {
-                    should_split = self.game_data.read().await.starting_new_file;
-                } else {
-                    let chapter_started = self.game_data.read().await.chapter_started;
+                    RunKind::FullGame => {
+                        should_split = conditions.check("starting a new file", self.game_data.read().await.state.starting_new_file);
+                    }
+                    RunKind::IndividualLevel => {
+                        let chapter_started = self.game_data.read().await.state.chapter_started;
 
-                    should_split = chapter_started && !last_chapter_started;
+                        conditions.add("chapter started", chapter_started);
+                        conditions.add("was started", last_chapter_started);
+                        should_split = chapter_started && !last_chapter_started;
 
-                    last_chapter_started = chapter_started;
+                        last_chapter_started = chapter_started;
+                    }
+                    RunKind::Manual => conditions.add("manual", "started from the timer"), // Started by the first split instead
+                }
+                if tracing {
+                    tracer.trace("Start", &format!("{run_kind:?} start"), &conditions, should_split);
+                    reason = should_split.then_some(conditions);
                 }
             } else {
-                let completed = self.game_data.read().await.chapter_complete;
-                let area_id = self.game_data.read().await.area_id;
+                let completed = self.game_data.read().await.state.chapter_complete;
+                let area_id = self.game_data.read().await.state.area_id;
                 let elapsed: f64 = {
                     if self.splits.file_time_offset {
-                        self.game_data.read().await.game_time - elapsed_offset
-                    } else if self.splits.il_splits {
-                        if self.game_data.read().await.area_id == Area::Menu {
+                        self.game_data.read().await.state.game_time - elapsed_offset
+                    } else if run_kind.is_chapter_timed() {
+                        if self.game_data.read().await.state.area_id == Area::Menu {
                             last_elapsed
                         } else {
-                            self.game_data.read().await.level_time
+                            self.game_data.read().await.state.level_time
                         }
                     } else {
-                        self.game_data.read().await.game_time
+                        self.game_data.read().await.state.game_time
                     }
                 };
-                let area_difficulty = self.game_data.read().await.area_difficulty;
-                let add_amount =
-                    (!self.splits.splits.is_empty() && !self.splits.chapter_splits) as i32;
+                let area_difficulty = self.game_data.read().await.state.area_difficulty;
+                let add_amount = run_kind.starts_on_first_split() as i32;
+                let split_index = *self.current_split.lock().await + add_amount;
                 let opt_split = self
                     .splits
                     .splits
-                    .get((*self.current_split.lock().await + add_amount) as usize);
-                let mut level_name = self.game_data.read().await.level_name.clone();
+                    .get(split_index as usize);
+                if last_split_index != Some(split_index) {
+                    debug!("Waiting for the split {split_index}: {opt_split:?}");
+                    last_split_index = Some(split_index);
+                }
+                let mut level_name = self.game_data.read().await.state.level_name.clone();
                 if level_name.is_empty() && area_id == Area::Menu {
                     level_name = last_level_name.clone()
                 };
-                let cassettes = self.game_data.read().await.cassettes;
-                let heart_gems = self.game_data.read().await.heart_gems;
-                let chapter_cassette = self.game_data.read().await.chapter_cassette_collected;
-                let chapter_heart = self.game_data.read().await.chapter_heart_collected;
-                //println!("{:?}", opt_split);
+                let cassettes = self.game_data.read().await.state.cassettes;
+                let heart_gems = self.game_data.read().await.state.heart_gems;
                 if let Some(split) = opt_split {
+                    let mut conditions = Conditions::new(tracing);
                     match split {
-                        Split::Manual => {}
-                        Split::LevelEnter { level } => should_split = area_id != Area::Menu && level_name != last_level_name && level.to_lowercase() == level_name.to_lowercase(),
-                        Split::LevelExit { level } => should_split = area_id != Area::Menu && level_name != last_level_name && level.to_lowercase() == last_level_name.to_lowercase(),
+                        Split::Manual => conditions.add("manual", "only split from the timer"),
+                        Split::Expression { expression } => {
+                            let state = &self.game_data.read().await.state;
+                            should_split = match last_state.as_ref() {
+                                Some(last_state) => expression.evaluate(state, last_state, conditions.expression()),
+                                None => {
+                                    conditions.add("previous state", "none yet");
+                                    false
+                                }
+                            }
+                        }
+                        Split::LevelEnter { level } | Split::LevelExit { level } => {
+                            let room = if matches!(split, Split::LevelEnter { .. }) { &level_name } else { &last_level_name };
+                            let in_chapter = conditions.check("in a chapter", area_id != Area::Menu);
+                            let room_changed = conditions.changed("room", level_name.as_str(), last_level_name.as_str());
+                            let right_room = conditions.expect("room to check", room.to_lowercase(), level.to_lowercase());
+                            should_split = in_chapter && room_changed && right_room
+                        }
                         Split::ChapterA => {
                             should_split = self.chapter_split(
                                 Area::Prologue,
@@ -263,6 +674,7 @@ impl LinSplitData {
                                 &level_name,
                                 completed,
                                 last_completed,
+                                &mut conditions,
                             ).await
                         }
                         Split::AreaComplete { area } => {
@@ -273,6 +685,7 @@ impl LinSplitData {
                                 &level_name,
                                 completed,
                                 last_completed,
+                                &mut conditions,
                             ).await
                         }
                         Split::AreaOnEnter { area } => {
@@ -283,6 +696,7 @@ impl LinSplitData {
                                 area_id,
                                 area_difficulty,
                                 area_difficulty,
+                                &mut conditions,
                             ).await
                         }
                         Split::AreaOnExit { area } => {
@@ -293,72 +707,54 @@ impl LinSplitData {
                                 *self.last_area_id.lock().await,
                                 area_difficulty,
                                 last_area_difficulty,
+                                &mut conditions,
                             ).await
                         }
-                        Split::Prologue => should_split = self.chapter_split(area_id, Area::Prologue, &level_name, completed, last_completed).await,
-                        Split::Chapter1 => should_split = self.chapter_split(area_id, Area::ForsakenCity, &level_name, completed, last_completed).await,
-                        Split::Chapter2 => should_split = self.chapter_split(area_id, Area::OldSite, &level_name, completed, last_completed).await,
-                        Split::Chapter3 => should_split = self.chapter_split(area_id, Area::CelestialResort, &level_name, completed, last_completed).await,
-                        Split::Chapter4 => should_split = self.chapter_split(area_id, Area::GoldenRidge, &level_name, completed, last_completed).await,
-                        Split::Chapter5 => should_split = self.chapter_split(area_id, Area::MirrorTemple, &level_name, completed, last_completed).await,
-                        Split::Chapter6 => should_split = self.chapter_split(area_id, Area::Reflection, &level_name, completed, last_completed).await,
-                        Split::Chapter7 => should_split = self.chapter_split(area_id, Area::TheSummit, &level_name, completed, last_completed).await,
-                        Split::Epilogue => should_split = self.chapter_split(area_id, Area::Epilogue, &level_name, completed, last_completed).await,
-                        Split::Chapter8 => should_split = self.chapter_split(area_id, Area::Core, &level_name, completed, last_completed).await,
-                        Split::Chapter9 => should_split = self.chapter_split(area_id, Area::Farewell, &level_name, completed, last_completed).await,
-                        Split::Chapter1Checkpoint1 => should_split = area_id == Area::ForsakenCity && ((area_difficulty == AreaMode::ASide && level_name == "6") || (area_difficulty != AreaMode::ASide && level_name == "04")),
-                        Split::Chapter1Checkpoint2 => should_split = area_id == Area::ForsakenCity && ((area_difficulty == AreaMode::ASide && level_name == "9b") || (area_difficulty != AreaMode::ASide && level_name == "08")),
-                        Split::Chapter2Checkpoint1 => should_split = area_id == Area::OldSite && ((area_difficulty == AreaMode::ASide && level_name == "3") || (area_difficulty != AreaMode::ASide && level_name == "03")),
-                        Split::Chapter2Checkpoint2 => should_split = area_id == Area::OldSite && ((area_difficulty == AreaMode::ASide && level_name == "end_3") || (area_difficulty != AreaMode::ASide && level_name == "08b")),
-                        Split::Chapter3Checkpoint1 => should_split = area_id == Area::CelestialResort && ((area_difficulty == AreaMode::ASide && level_name == "08-a") || (area_difficulty != AreaMode::ASide && level_name == "06")),
-                        Split::Chapter3Checkpoint2 => should_split = area_id == Area::CelestialResort && ((area_difficulty == AreaMode::ASide && level_name == "09-d") || (area_difficulty != AreaMode::ASide && level_name == "11")),
-                        Split::Chapter3Checkpoint3 => should_split = area_id == Area::CelestialResort && ((area_difficulty == AreaMode::ASide && level_name == "00-d") || (area_difficulty != AreaMode::ASide && level_name == "16")),
-                        Split::Chapter4Checkpoint1 => should_split = area_id == Area::GoldenRidge && level_name == "b-00",
-                        Split::Chapter4Checkpoint2 => should_split = area_id == Area::GoldenRidge && level_name == "c-00",
-                        Split::Chapter4Checkpoint3 => should_split = area_id == Area::GoldenRidge && level_name == "d-00",
-                        Split::Chapter5Checkpoint1 => should_split = area_id == Area::MirrorTemple && level_name == "b-00",
-                        Split::Chapter5Checkpoint2 => should_split = area_id == Area::MirrorTemple && level_name == "c-00",
-                        Split::Chapter5Checkpoint3 => should_split = area_id == Area::MirrorTemple && level_name == "d-00",
-                        Split::Chapter5Checkpoint4 => should_split = area_id == Area::MirrorTemple && level_name == "e-00",
-                        Split::Chapter6Checkpoint1 => should_split = area_id == Area::Reflection && ((area_difficulty == AreaMode::ASide && level_name == "00") || (area_difficulty != AreaMode::ASide && level_name == "b-00")),
-                        Split::Chapter6Checkpoint2 => should_split = area_id == Area::Reflection && ((area_difficulty == AreaMode::ASide && level_name == "04") || (area_difficulty != AreaMode::ASide && level_name == "c-00")),
-                        Split::Chapter6Checkpoint3 => should_split = area_id == Area::Reflection && ((area_difficulty == AreaMode::ASide && level_name == "b-00") || (area_difficulty != AreaMode::ASide && level_name == "d-00")),
-                        Split::Chapter6Checkpoint4 => should_split = area_id == Area::Reflection && level_name == "boss-00",
-                        Split::Chapter6Checkpoint5 => should_split = area_id == Area::Reflection && level_name == "after-00",
-                        Split::Chapter7Checkpoint1 => should_split = area_id == Area::TheSummit && level_name == "b-00",
-                        Split::Chapter7Checkpoint2 => should_split = area_id == Area::TheSummit && ((area_difficulty == AreaMode::ASide && level_name == "c-00") || (area_difficulty != AreaMode::ASide && level_name == "c-01")),
-                        Split::Chapter7Checkpoint3 => should_split = area_id == Area::TheSummit && level_name == "d-00",
-                        Split::Chapter7Checkpoint4 => should_split = area_id == Area::TheSummit && ((area_difficulty == AreaMode::ASide && level_name == "e-00b") || (area_difficulty != AreaMode::ASide && level_name == "e-00")),
-                        Split::Chapter7Checkpoint5 => should_split = area_id == Area::TheSummit && level_name == "f-00",
-                        Split::Chapter7Checkpoint6 => should_split = area_id == Area::TheSummit && level_name == "g-00",
-                        Split::Chapter8Checkpoint1 => should_split = area_id == Area::Core && level_name == "a-00",
-                        Split::Chapter8Checkpoint2 => should_split = area_id == Area::Core && ((area_difficulty == AreaMode::ASide && level_name == "c-00") || (area_difficulty != AreaMode::ASide && level_name == "b-00")),
-                        Split::Chapter8Checkpoint3 => should_split = area_id == Area::Core && ((area_difficulty == AreaMode::ASide && level_name == "d-00") || (area_difficulty != AreaMode::ASide && level_name == "c-01")),
-                        Split::Chapter9Checkpoint1 => should_split = area_id == Area::Farewell && level_name == "a-00",
-                        Split::Chapter9Checkpoint2 => should_split = area_id == Area::Farewell && level_name == "c-00",
-                        Split::Chapter9Checkpoint3 => should_split = area_id == Area::Farewell && level_name == "e-00z",
-                        Split::Chapter9Checkpoint4 => should_split = area_id == Area::Farewell && level_name == "f-door",
-                        Split::Chapter9Checkpoint5 => should_split = area_id == Area::Farewell && level_name == "h-00b",
-                        Split::Chapter9Checkpoint6 => should_split = area_id == Area::Farewell && level_name == "i-00",
-                        Split::Chapter9Checkpoint7 => should_split = area_id == Area::Farewell && level_name == "j-00",
-                        Split::Chapter9Checkpoint8 => should_split = area_id == Area::Farewell && level_name == "j-16",
-                        Split::HeartGemAny => should_split = ((self.splits.il_splits || self.splits.file_time_offset) && chapter_heart) || heart_gems == last_heart_gems + 1,
-                        Split::Chapter1Cassette => should_split = area_id == Area::ForsakenCity && (((self.splits.il_splits || self.splits.file_time_offset) && chapter_cassette) || cassettes == last_cassettes + 1),
-                        Split::Chapter1HeartGem => should_split = area_id == Area::ForsakenCity && (((self.splits.il_splits || self.splits.file_time_offset) && chapter_heart) || heart_gems == last_heart_gems + 1),
-                        Split::Chapter2Cassette => should_split = area_id == Area::OldSite && (((self.splits.il_splits || self.splits.file_time_offset) && chapter_cassette) || cassettes == last_cassettes + 1),
-                        Split::Chapter2HeartGem => should_split = area_id == Area::OldSite && (((self.splits.il_splits || self.splits.file_time_offset) && chapter_heart) || heart_gems == last_heart_gems + 1),
-                        Split::Chapter3Cassette => should_split = area_id == Area::CelestialResort && (((self.splits.il_splits || self.splits.file_time_offset) && chapter_cassette) || cassettes == last_cassettes + 1),
-                        Split::Chapter3HeartGem => should_split = area_id == Area::CelestialResort && (((self.splits.il_splits || self.splits.file_time_offset) && chapter_heart) || heart_gems == last_heart_gems + 1),
-                        Split::Chapter4Cassette => should_split = area_id == Area::GoldenRidge && (((self.splits.il_splits || self.splits.file_time_offset) && chapter_cassette) || cassettes == last_cassettes + 1),
-                        Split::Chapter4HeartGem => should_split = area_id == Area::GoldenRidge && (((self.splits.il_splits || self.splits.file_time_offset) && chapter_heart) || heart_gems == last_heart_gems + 1),
-                        Split::Chapter5Cassette => should_split = area_id == Area::MirrorTemple && (((self.splits.il_splits || self.splits.file_time_offset) && chapter_cassette) || cassettes == last_cassettes + 1),
-                        Split::Chapter5HeartGem => should_split = area_id == Area::MirrorTemple && (((self.splits.il_splits || self.splits.file_time_offset) && chapter_heart) || heart_gems == last_heart_gems + 1),
-                        Split::Chapter6Cassette => should_split = area_id == Area::Reflection && (((self.splits.il_splits || self.splits.file_time_offset) && chapter_cassette) || cassettes == last_cassettes + 1),
-                        Split::Chapter6HeartGem => should_split = area_id == Area::Reflection && (((self.splits.il_splits || self.splits.file_time_offset) && chapter_heart) || heart_gems == last_heart_gems + 1),
-                        Split::Chapter7Cassette => should_split = area_id == Area::TheSummit && (((self.splits.il_splits || self.splits.file_time_offset) && chapter_cassette) || cassettes == last_cassettes + 1),
-                        Split::Chapter7HeartGem => should_split = area_id == Area::TheSummit && (((self.splits.il_splits || self.splits.file_time_offset) && chapter_heart) || heart_gems == last_heart_gems + 1),
-                        Split::Chapter8Cassette => should_split = area_id == Area::Core && (((self.splits.il_splits || self.splits.file_time_offset) && chapter_cassette) || cassettes == last_cassettes + 1),
-                        Split::Chapter8HeartGem => should_split = area_id == Area::Core && (((self.splits.il_splits || self.splits.file_time_offset) && chapter_heart) || heart_gems == last_heart_gems + 1),
+                        Split::Prologue => should_split = self.chapter_split(area_id, Area::Prologue, &level_name, completed, last_completed, &mut conditions).await,
+                        Split::Chapter1 => should_split = self.chapter_split(area_id, Area::ForsakenCity, &level_name, completed, last_completed, &mut conditions).await,
+                        Split::Chapter2 => should_split = self.chapter_split(area_id, Area::OldSite, &level_name, completed, last_completed, &mut conditions).await,
+                        Split::Chapter3 => should_split = self.chapter_split(area_id, Area::CelestialResort, &level_name, completed, last_completed, &mut conditions).await,
+                        Split::Chapter4 => should_split = self.chapter_split(area_id, Area::GoldenRidge, &level_name, completed, last_completed, &mut conditions).await,
+                        Split::Chapter5 => should_split = self.chapter_split(area_id, Area::MirrorTemple, &level_name, completed, last_completed, &mut conditions).await,
+                        Split::Chapter6 => should_split = self.chapter_split(area_id, Area::Reflection, &level_name, completed, last_completed, &mut conditions).await,
+                        Split::Chapter7 => should_split = self.chapter_split(area_id, Area::TheSummit, &level_name, completed, last_completed, &mut conditions).await,
+                        Split::Epilogue => should_split = self.chapter_split(area_id, Area::Epilogue, &level_name, completed, last_completed, &mut conditions).await,
+                        Split::Chapter8 => should_split = self.chapter_split(area_id, Area::Core, &level_name, completed, last_completed, &mut conditions).await,
+                        Split::Chapter9 => should_split = self.chapter_split(area_id, Area::Farewell, &level_name, completed, last_completed, &mut conditions).await,
+                        split if let Some((area, mode, index)) = split.checkpoint() => {
+                            let right_area = conditions.expect("area", area_id, area);
+                            let right_side = match mode {
+                                Some(mode) => conditions.expect("side", area_difficulty, mode),
+                                None => {
+                                    conditions.add("side", format!("{area_difficulty:?}"));
+                                    true
+                                }
+                            };
+                            let right_room = match checkpoints::find(area, area_difficulty, index) {
+                                Some(checkpoint) => conditions.expect("room", level_name.as_str(), checkpoint.room),
+                                None => {
+                                    conditions.add("room", format!("this side has no checkpoint {index}, never splits"));
+                                    false
+                                }
+                            };
+                            should_split = right_area && right_side && right_room
+                        }
+                        Split::HeartGemAny => should_split = self.collectible_split(&self.game_data.read().await.state, None, false, last_heart_gems, &mut conditions),
+                        Split::Chapter1Cassette | Split::Chapter2Cassette | Split::Chapter3Cassette | Split::Chapter4Cassette
+                        | Split::Chapter5Cassette | Split::Chapter6Cassette | Split::Chapter7Cassette | Split::Chapter8Cassette => {
+                            should_split = self.collectible_split(&self.game_data.read().await.state, split.area(), true, last_cassettes, &mut conditions)
+                        }
+                        Split::Chapter1HeartGem | Split::Chapter2HeartGem | Split::Chapter3HeartGem | Split::Chapter4HeartGem
+                        | Split::Chapter5HeartGem | Split::Chapter6HeartGem | Split::Chapter7HeartGem | Split::Chapter8HeartGem => {
+                            should_split = self.collectible_split(&self.game_data.read().await.state, split.area(), false, last_heart_gems, &mut conditions)
+                        }
+                        // Only the checkpoint splits are left, and they all have a checkpoint
+                        _ => conditions.add("split", "not a split linsplit checks, never splits"),
+                    }
+                    if tracing {
+                        let segment = usize::try_from(split_index - add_amount).ok().and_then(|index| self.splits.segment_names.get(index));
+                        tracer.trace(segment.map_or("Start", String::as_str), &split_trace::label(split), &conditions, should_split);
+                        reason = should_split.then_some(conditions);
                     }
                     last_cassettes = cassettes;
                     last_heart_gems = heart_gems;
@@ -367,49 +763,153 @@ impl LinSplitData {
                 }
 
                 if should_split && add_amount > 0 && *self.current_split.lock().await < 0 {
-                    level_timer = self.game_data.read().await.level_time;
+                    level_timer = self.game_data.read().await.state.level_time;
                 }
 
                 last_completed = completed;
                 last_level_name = level_name;
 
+                // Followed on every update, the chapter's timer is only sent once it's started
+                if run_kind.is_chapter_timed() && !self.splits.file_time_offset {
+                    let chapter_started = self.game_data.read().await.state.chapter_started;
+                    time_watch.enter_chapter(area_id, area_difficulty, chapter_started);
+                }
                 if elapsed > 0. || last_elapsed == elapsed {
-                    self.socket
+                    let mut game_time = if run_kind.is_relative_to_start() {
+                        elapsed - level_timer
+                    } else {
+                        elapsed
+                    };
+                    if *self.current_split.lock().await < 0 {
+                        time_watch.reset();
+                    } else {
+                        let (time, regression) = time_watch.check(game_time, self.time_regression);
+                        game_time = time;
+                        if let Some(regression) = regression {
+                            warn!("The game time went back from {:.3} to {:.3}", regression.from, regression.to);
+                            time_went_back = true;
+                        }
+                    }
+                    *self.game_time.lock().await = game_time;
+                    self.timer
                         .send_command(Command::SetGameTime {
-                            time: TimeSpan::from_seconds(
-                                if self.splits.splits.is_empty() || add_amount > 0 {
-                                    elapsed - level_timer
-                                } else {
-                                    elapsed
-                                },
-                            ),
+                            time: TimeSpan::from_seconds(game_time),
                         })
-                        .await
-                        .unwrap();
+                        .await?;
                 }
 
                 last_elapsed = elapsed;
             }
-            let should_reset = self.splits.auto_reset
-                && self.splits.il_splits
-                && self.game_data.read().await.area_id == Area::Menu;
+            if self.forbid_assists {
+                let forbidden_modes = self.game_data.read().await.state.forbidden_modes();
+                if !forbidden_modes.is_empty() {
+                    should_split = false;
+                    if forbidden_modes != last_forbidden_modes {
+                        self.timer
+                            .send_command(Command::SetCustomVariable {
+                                key: String::from(INVALID_RUN_VARIABLE),
+                                value: forbidden_modes.join(", "),
+                            })
+                            .await?;
+                        marked_invalid = true;
+                    }
+                } else if marked_invalid && *self.current_split.lock().await < 0 {
+                    // The run was reset, the next one can be valid
+                    self.timer
+                        .send_command(Command::SetCustomVariable {
+                            key: String::from(INVALID_RUN_VARIABLE),
+                            value: String::new(),
+                        })
+                        .await?;
+                    marked_invalid = false;
+                }
+                last_forbidden_modes = forbidden_modes;
+            }
+            if time_went_back && self.time_regression == TimeRegressionPolicy::Practice {
+                if let Some(attempt) = self.attempt.lock().await.as_mut() {
+                    attempt.practice = true;
+                }
+                if !marked_practice {
+                    self.timer
+                        .send_command(Command::SetCustomVariable {
+                            key: String::from(PRACTICE_VARIABLE),
+                            value: String::from("the game time went back"),
+                        })
+                        .await?;
+                    marked_practice = true;
+                }
+            } else if marked_practice && *self.current_split.lock().await < 0 {
+                self.timer
+                    .send_command(Command::SetCustomVariable {
+                        key: String::from(PRACTICE_VARIABLE),
+                        value: String::new(),
+                    })
+                    .await?;
+                marked_practice = false;
+            }
+            let index = *self.current_split.lock().await;
+            let in_menu = self.game_data.read().await.state.area_id == Area::Menu;
+            if index < 0 || !in_menu {
+                reset_requested = false;
+            }
+            // Resetting a running attempt once, rather than on every update until the timer did it
+            let should_reset = (time_went_back && self.time_regression == TimeRegressionPolicy::Reset)
+                || (self.splits.auto_reset
+                    && run_kind.is_chapter_timed()
+                    && in_menu
+                    && index >= 0
+                    && !reset_requested);
+            if should_reset {
+                info!("Resetting the run on split {}", index + 1);
+                self.emit(StreamEvent::Reset { index });
+            } else if should_split {
+                let segment = usize::try_from(index).ok().and_then(|index| self.splits.segment_names.get(index)).cloned();
+                match &segment {
+                    Some(segment) => info!("Splitting at the end of \"{segment}\""),
+                    None => info!("Starting the run"),
+                }
+                self.emit(StreamEvent::Split { index, segment });
+            }
             let mut chap = self.exiting_chapter.lock().await;
             if should_reset {
-                self.socket
+                self.timer
                     .send_command(Command::Reset {
                         save_attempt: Some(true),
                     })
-                    .await
-                    .unwrap();
+                    .await?;
+                reset_requested = true;
                 *chap = false;
             } else if should_split {
-                self.socket
+                if let Some(conditions) = reason {
+                    self.timer.explain_next_split(conditions).await;
+                }
+                *self.split_sent.lock().await = Some((*self.game_time.lock().await, self.game_data.read().await.state.clone()));
+                self.timer
                     .send_command(Command::SplitOrStart)
-                    .await
-                    .unwrap();
+                    .await?;
                 *chap = false;
             }
-            tokio::time::sleep(Duration::from_millis(1)).await;
+            last_state = Some(self.game_data.read().await.state.clone());
+            self.wait_for_next_update().await;
         }
     }
 }
+
+/// Parses the `Area` or `Area-Side` argument of the area splits.
+fn area_argument(argument: &str, conditions: &mut Conditions) -> Option<(Area, Option<AreaMode>)> {
+    let parsed = match argument.split('-').collect::<Vec<_>>()[..] {
+        [area] => Area::from_str(area.trim()).ok().map(|area| (area, None)),
+        [area, mode] => Area::from_str(area.trim())
+            .ok()
+            .zip(AreaMode::from_str(mode.trim()).ok())
+            .map(|(area, mode)| (area, Some(mode))),
+        _ => None,
+    };
+    if parsed.is_none() {
+        conditions.add(
+            "argument",
+            format!("\"{argument}\" isn't an area, never splits"),
+        );
+    }
+    parsed
+}
@@ -0,0 +1,209 @@
+use anyhow::{Result, anyhow};
+use strum::{EnumMessage, EnumProperty, IntoEnumIterator};
+
+use crate::split_reader::checkpoints::CHECKPOINTS;
+use crate::split_reader::expression::{SIDE_LETTERS, Variable};
+use crate::split_reader::{Area, AreaMode, Split};
+
+/// Splits that can't be listed from the parser's names, with an example the parser accepts.
+const SPECIAL_SPLITS: [(&str, &str, &str, &str); 2] = [
+    (
+        "Checkpoint",
+        "Area,Side,N",
+        "Reaching the Nth checkpoint of a given side, see the checkpoints below",
+        "Checkpoint,Core,BSide,2",
+    ),
+    (
+        "Expression",
+        "Condition",
+        "A custom condition on the variables below, combined with && || ! and parentheses",
+        "Expression,area == Core && side == B && room == c-01",
+    ),
+];
+
+/// A kind of split, as written in the splits file.
+#[derive(serde_derive::Serialize)]
+struct SplitKind {
+    name: &'static str,
+    argument: Option<&'static str>,
+    description: String,
+    example: String,
+}
+
+/// An area or side, with every spelling the parser accepts.
+#[derive(serde_derive::Serialize)]
+struct Spellings {
+    name: String,
+    id: i32,
+    spellings: Vec<&'static str>,
+    /// Spellings only accepted in the custom conditions.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    condition_spellings: Vec<&'static str>,
+}
+
+#[derive(serde_derive::Serialize)]
+struct CheckpointRoom {
+    area: Area,
+    side: AreaMode,
+    index: u8,
+    room: &'static str,
+    name: &'static str,
+}
+
+#[derive(serde_derive::Serialize)]
+struct Catalogue {
+    splits: Vec<SplitKind>,
+    areas: Vec<Spellings>,
+    sides: Vec<Spellings>,
+    checkpoints: Vec<CheckpointRoom>,
+    variables: Vec<&'static str>,
+}
+
+/// What a split without documentation does, from the chapter or checkpoint it refers to.
+fn describe(split: &Split, name: &str) -> Result<String> {
+    if let Some(documentation) = split.get_documentation() {
+        return Ok(documentation.trim().trim_end_matches('.').to_string());
+    }
+    let area = split
+        .area()
+        .ok_or(anyhow!("the split {name} has no documentation"))?;
+    Ok(match split.checkpoint() {
+        Some((_, _, index)) => {
+            let rooms: Vec<_> = CHECKPOINTS
+                .iter()
+                .filter(|checkpoint| checkpoint.area == area && checkpoint.index == index)
+                .map(|checkpoint| format!("{:?} {}", checkpoint.mode, checkpoint.room))
+                .collect();
+            format!(
+                "Reaching checkpoint {index} of {area:?} on any side (rooms: {})",
+                rooms.join(", ")
+            )
+        }
+        None if name.ends_with("Cassette") => format!("Collecting the cassette of {area:?}"),
+        None if name.ends_with("HeartGem") => format!("Collecting the crystal heart of {area:?}"),
+        None => format!("Completing {area:?}"),
+    })
+}
+
+fn catalogue() -> Result<Catalogue> {
+    let mut splits = Vec::new();
+    for split in Split::iter() {
+        let name: &'static str = (&split).into();
+        let argument = split.get_str("argument");
+        splits.push(SplitKind {
+            name,
+            argument,
+            description: describe(&split, name)?,
+            example: split
+                .get_str("example")
+                .map_or(name.to_string(), str::to_string),
+        });
+    }
+    for (name, argument, description, example) in SPECIAL_SPLITS {
+        splits.push(SplitKind {
+            name,
+            argument: Some(argument),
+            description: description.to_string(),
+            example: example.to_string(),
+        });
+    }
+    // The examples are checked against the parser, so that they can't get out of date
+    for split in &splits {
+        Split::from_str_field(&split.example)
+            .map_err(|e| anyhow!("the example of {} is invalid: {e}", split.name))?;
+    }
+    Ok(Catalogue {
+        splits,
+        // Unknown and None are what the game reports outside of the chapters, not spellings
+        areas: Area::iter()
+            .filter(|&area| area != Area::Unknown)
+            .map(|area| Spellings {
+                name: format!("{area:?}"),
+                id: area as i32,
+                spellings: area.get_serializations().to_vec(),
+                condition_spellings: vec![],
+            })
+            .collect(),
+        sides: AreaMode::iter()
+            .filter(|&mode| !matches!(mode, AreaMode::Unknown | AreaMode::None))
+            .map(|mode| Spellings {
+                name: format!("{mode:?}"),
+                id: mode as i32,
+                spellings: mode.get_serializations().to_vec(),
+                condition_spellings: SIDE_LETTERS
+                    .iter()
+                    .filter(|&&(_, side)| side == mode)
+                    .map(|&(letter, _)| letter)
+                    .collect(),
+            })
+            .collect(),
+        checkpoints: CHECKPOINTS
+            .iter()
+            .map(|checkpoint| CheckpointRoom {
+                area: checkpoint.area,
+                side: checkpoint.mode,
+                index: checkpoint.index,
+                room: checkpoint.room,
+                name: checkpoint.name,
+            })
+            .collect(),
+        variables: Variable::ALL.iter().map(|&(name, _)| name).collect(),
+    })
+}
+
+/// `linsplit list-splits`: prints every split the splits file can contain.
+pub fn run(json: bool) -> Result<()> {
+    let catalogue = catalogue()?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&catalogue)?);
+        return Ok(());
+    }
+
+    println!("Splits (written as the text of a <Split>, with the argument after a comma)");
+    for split in &catalogue.splits {
+        let syntax = match split.argument {
+            Some(argument) => format!("{},{argument}", split.name),
+            None => split.name.to_string(),
+        };
+        println!("  {syntax:<28} {}", split.description);
+        if split.argument.is_some() {
+            println!("  {:<28} e.g. {}", "", split.example);
+        }
+    }
+
+    println!();
+    println!("Areas (names are case-insensitive)");
+    for area in &catalogue.areas {
+        println!("  {:<16} {}", area.name, area.spellings.join(", "));
+    }
+    println!();
+    println!("Sides (names are case-insensitive)");
+    for side in &catalogue.sides {
+        let mut spellings = side.spellings.join(", ");
+        if !side.condition_spellings.is_empty() {
+            spellings += &format!(
+                " (and {} in conditions)",
+                side.condition_spellings.join(", ")
+            );
+        }
+        println!("  {:<16} {spellings}", side.name);
+    }
+
+    println!();
+    println!("Checkpoints (area, side, number: room)");
+    for checkpoint in &catalogue.checkpoints {
+        println!(
+            "  {:<16} {:<6} {}: {:<9} {}",
+            format!("{:?}", checkpoint.area),
+            format!("{:?}", checkpoint.side),
+            checkpoint.index,
+            checkpoint.room,
+            checkpoint.name
+        );
+    }
+
+    println!();
+    println!("Variables of the custom conditions");
+    println!("  {}", catalogue.variables.join(", "));
+    Ok(())
+}
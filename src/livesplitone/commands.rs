@@ -1,9 +1,11 @@
 use serde::Serializer;
-use std::time::Duration;
 
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+
+/// A signed duration in nanoseconds, so that a time going backwards can be represented.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 #[repr(transparent)]
-pub struct TimeSpan(Duration);
+pub struct TimeSpan(i64);
 
 #[derive(Clone, serde_derive::Serialize)]
 #[repr(u8)]
@@ -30,15 +32,31 @@ pub enum Command {
         #[serde(serialize_with = "serialize_time_span")]
         time: TimeSpan,
     },
+    PauseGameTime,
+    SetCustomVariable {
+        /// The name of the custom variable.
+        key: String,
+        /// The value of the custom variable.
+        value: String,
+    },
 }
 
 impl TimeSpan {
+    /// Whole seconds and the remaining nanoseconds, both with the sign of the time span.
     pub const fn to_seconds_and_subsec_nanoseconds(self) -> (i64, i32) {
-        (self.0.as_secs() as i64, self.0.subsec_nanos() as i32)
+        (
+            self.0 / NANOS_PER_SECOND,
+            (self.0 % NANOS_PER_SECOND) as i32,
+        )
     }
 
+    /// Never panics: out of range values saturate, and NaN is zero.
     pub fn from_seconds(seconds: f64) -> Self {
-        Self(Duration::from_secs_f64(seconds))
+        Self((seconds * NANOS_PER_SECOND as f64).round() as i64)
+    }
+
+    pub fn to_seconds(self) -> f64 {
+        self.0 as f64 / NANOS_PER_SECOND as f64
     }
 }
 
@@ -47,7 +65,13 @@ fn serialize_time_span<S: Serializer>(
     serializer: S,
 ) -> Result<S::Ok, S::Error> {
     let (secs, nanos) = time_span.to_seconds_and_subsec_nanoseconds();
-    serializer.collect_str(&format_args!("{secs}.{:09}", nanos.abs()))
+    // The sign is lost on the seconds of the times between -1 and 0
+    let sign = if time_span.0 < 0 { "-" } else { "" };
+    serializer.collect_str(&format_args!(
+        "{sign}{}.{:09}",
+        secs.unsigned_abs(),
+        nanos.unsigned_abs()
+    ))
 }
 
 #[allow(unused)]
@@ -139,3 +163,55 @@ pub enum EventError {
     #[serde(other)]
     Unknown,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set_game_time(seconds: f64) -> String {
+        serde_json::to_string(&Command::SetGameTime {
+            time: TimeSpan::from_seconds(seconds),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn game_time_is_sent_with_nanoseconds() {
+        assert_eq!(
+            set_game_time(1.5),
+            r#"{"command":"setGameTime","time":"1.500000000"}"#
+        );
+        assert_eq!(
+            set_game_time(-0.5),
+            r#"{"command":"setGameTime","time":"-0.500000000"}"#
+        );
+        assert_eq!(
+            set_game_time(-61.25),
+            r#"{"command":"setGameTime","time":"-61.250000000"}"#
+        );
+    }
+
+    #[test]
+    fn seconds_never_panic() {
+        assert_eq!(TimeSpan::from_seconds(f64::NAN), TimeSpan(0));
+        assert_eq!(TimeSpan::from_seconds(f64::INFINITY), TimeSpan(i64::MAX));
+        assert_eq!(TimeSpan::from_seconds(-1e300), TimeSpan(i64::MIN));
+        assert_eq!(TimeSpan::from_seconds(2.25).to_seconds(), 2.25);
+    }
+
+    #[test]
+    fn reads_events_and_errors() {
+        let event: Event = serde_json::from_str(r#"{"event":"Splitted"}"#).unwrap();
+        assert!(matches!(event, Event::Splitted));
+        let event: Event = serde_json::from_str(r#"{"event":"SomethingNew"}"#).unwrap();
+        assert!(matches!(event, Event::Unknown));
+        let result: CommandResult<Response, CommandError> =
+            serde_json::from_str(r#"{"error":{"code":"RunAlreadyInProgress"}}"#).unwrap();
+        assert!(matches!(
+            result,
+            CommandResult::Error(CommandError::Timer {
+                code: EventError::RunAlreadyInProgress
+            })
+        ));
+    }
+}
@@ -1,6 +1,12 @@
 pub mod commands;
 
-use std::{collections::VecDeque, sync::Arc};
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
 
 use tokio::sync::{Mutex, Notify};
 
@@ -9,6 +15,7 @@ use futures_util::{
     SinkExt, TryStreamExt,
     stream::{SplitSink, SplitStream, StreamExt},
 };
+use log::{info, trace, warn};
 use tokio::net::{TcpListener, TcpStream};
 use tokio_tungstenite::{WebSocketStream, tungstenite::protocol::Message};
 
@@ -21,6 +28,7 @@ pub struct SplitterSocket {
     response_notification: Notify,
     events: Arc<Mutex<VecDeque<Event>>>,
     event_notifications: Arc<Notify>,
+    connected: AtomicBool,
 }
 
 impl SplitterSocket {
@@ -30,14 +38,14 @@ impl SplitterSocket {
         event_notifications: Arc<Notify>,
     ) -> Result<Arc<Self>> {
         let socket: TcpListener = TcpListener::bind(&addr).await?;
-        println!("Waiting for LiveSplitOne Connection...");
-        println!("Enter ws://{addr} in the LiveSplitOne \"Server Connection\" setting");
+        info!("Waiting for LiveSplitOne Connection...");
+        info!("Enter ws://{addr} in the LiveSplitOne \"Server Connection\" setting");
         if let Ok((stream, _addr)) = socket.accept().await {
             let ws_stream = tokio_tungstenite::accept_async(stream)
                 .await
                 .expect("websocket failed");
             let (outcoming, incoming) = ws_stream.split();
-            println!("Connected to LiveSplitOne");
+            info!("Connected to LiveSplitOne");
             let sock = Arc::new(SplitterSocket {
                 outcoming: Mutex::new(outcoming),
                 incoming: Mutex::new(incoming),
@@ -45,6 +53,7 @@ impl SplitterSocket {
                 response_notification: Notify::new(),
                 event_notifications,
                 events,
+                connected: AtomicBool::new(true),
             });
             let reader = Arc::clone(&sock);
             tokio::spawn(async move { reader.listener_loop().await });
@@ -57,10 +66,12 @@ impl SplitterSocket {
         &self,
         command: Command,
     ) -> Result<Option<CommandResult<Response, CommandError>>> {
+        let message = serde_json::to_string(&command)?;
+        trace!("Sending {message}");
         self.outcoming
             .lock()
             .await
-            .send(Message::text(serde_json::to_string(&command)?))
+            .send(Message::text(message))
             .await?;
         // Wait for the response
         self.response_notification.notified().await;
@@ -68,9 +79,32 @@ impl SplitterSocket {
         Ok(response)
     }
 
+    /// Whether LiveSplit One is still connected.
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// Closes the connection with a close frame, so that LiveSplit One knows it wasn't lost.
+    pub async fn close(&self) -> Result<()> {
+        self.outcoming.lock().await.close().await?;
+        Ok(())
+    }
+
     async fn listener_loop(self: Arc<Self>) {
         loop {
-            if let Ok(Some(Message::Text(message))) = self.incoming.lock().await.try_next().await {
+            let message = self.incoming.lock().await.try_next().await;
+            if let Err(error) = &message {
+                warn!("Error on the LiveSplitOne connection: {error}");
+            }
+            if let Ok(None) | Err(_) = message {
+                warn!("Disconnected from LiveSplitOne");
+                self.connected.store(false, Ordering::Relaxed);
+                // A command waiting for its response won't get one
+                self.response_notification.notify_one();
+                return;
+            }
+            if let Ok(Some(Message::Text(message))) = message {
+                trace!("Received {message}");
                 if let Ok(response) =
                     serde_json::from_str::<CommandResult<Response, CommandError>>(&message)
                 {
@@ -0,0 +1,160 @@
+use anyhow::{Result, anyhow};
+use log::LevelFilter;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Modules that can be named without the `linsplit::` prefix in `RUST_LOG`.
+const MODULES: [&str; 12] = [
+    "memory_reader",
+    "save_reader",
+    "split_reader",
+    "livesplitone",
+    "linsplit_data",
+    "split_trace",
+    "event_stream",
+    "http_server",
+    "obs_output",
+    "room_times",
+    "history",
+    "practice",
+];
+
+/// Size a log file can reach before it's rotated.
+const MAX_LOG_SIZE: u64 = 5 * 1024 * 1024;
+
+/// How many rotated log files are kept, as `linsplit.log.1`, `linsplit.log.2`...
+const ROTATED_LOGS: usize = 3;
+
+/// Starts logging to the standard error, and to `file` if given.
+///
+/// `verbosity` is the number of `-v` minus the number of `-q`. `RUST_LOG` can then change the
+/// level of every module, like `RUST_LOG=info,memory_reader=debug`.
+pub fn init(verbosity: i8, file: Option<&Path>) -> Result<()> {
+    let level = match verbosity {
+        ..=-2 => LevelFilter::Error,
+        -1 => LevelFilter::Warn,
+        0 => LevelFilter::Info,
+        1 => LevelFilter::Debug,
+        2.. => LevelFilter::Trace,
+    };
+    let file = file.map(LogFile::open).transpose()?;
+    let mut builder = env_logger::Builder::new();
+    // The dependencies only say something useful when something goes wrong
+    builder
+        .filter_level(LevelFilter::Warn)
+        .filter_module("linsplit", level)
+        .format_timestamp_millis()
+        .target(env_logger::Target::Pipe(Box::new(Output { file })));
+    if let Ok(filters) = std::env::var("RUST_LOG") {
+        builder.parse_filters(&expand_modules(&filters));
+    }
+    builder
+        .try_init()
+        .map_err(|e| anyhow!("couldn't start logging: {e}"))
+}
+
+/// Adds the `linsplit::` prefix to the modules of [`MODULES`] in the filters.
+fn expand_modules(filters: &str) -> String {
+    filters
+        .split(',')
+        .map(|directive| {
+            let module = directive.split('=').next().unwrap_or_default().trim();
+            let ours = MODULES
+                .iter()
+                .any(|name| module == *name || module.starts_with(&format!("{name}::")));
+            if ours {
+                format!("linsplit::{}", directive.trim())
+            } else {
+                directive.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Writes the logs to the standard error and to the log file.
+struct Output {
+    file: Option<LogFile>,
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::stderr().write_all(buf)?;
+        if let Some(file) = &mut self.file {
+            file.write_all(buf)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()?;
+        if let Some(file) = &mut self.file {
+            file.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// A log file, rotated when it gets bigger than [`MAX_LOG_SIZE`].
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl LogFile {
+    fn open(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| anyhow!("couldn't create {}: {e}", dir.display()))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| anyhow!("couldn't open {}: {e}", path.display()))?;
+        let size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+        Ok(LogFile {
+            path: path.to_path_buf(),
+            file,
+            size,
+        })
+    }
+
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{index}"));
+        PathBuf::from(path)
+    }
+
+    /// Moves `linsplit.log` to `linsplit.log.1`, `linsplit.log.1` to `linsplit.log.2`... and
+    /// starts a new file.
+    fn rotate(&mut self) -> io::Result<()> {
+        for index in (1..ROTATED_LOGS).rev() {
+            let from = self.rotated(index);
+            if from.exists() {
+                fs::rename(from, self.rotated(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated(1))?;
+        self.file = File::create(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for LogFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > MAX_LOG_SIZE {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
@@ -1,59 +1,395 @@
 #![forbid(unsafe_code)]
+mod config;
+mod doctor;
+mod event_stream;
+mod generate;
+mod history;
+mod http_server;
 mod linsplit_data;
+mod list_splits;
 mod livesplitone;
-mod memory_reader;
+mod logging;
+mod obs_output;
+mod practice;
+mod room_times;
+mod simulate;
 mod split_reader;
+mod split_trace;
+mod time_regression;
+mod timer;
 
+use linsplit::{memory_reader, save_reader};
+
+use crate::config::{ConfigFile, Profile, TimeRegressionPolicy, TimerBackend};
 use crate::linsplit_data::LinSplitData;
-use clap::Parser;
+use crate::split_reader::{RunKind, SettingsOverrides};
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use expand_tilde::expand_tilde;
+use log::{error, info};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use tokio::signal::unix::{SignalKind, signal};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
 struct Args {
     /// Path for the splits (.lss file) to read data from
-    #[arg(short = 's', long = "splits", value_name = "PATH", required = true)]
-    splits: String,
+    #[arg(short = 's', long = "splits", value_name = "PATH", global = true)]
+    splits: Option<String>,
 
-    /// Address to listen to, for LiveSplitOne to connect to.
-    #[arg(
-        short = 'a',
-        long = "address",
-        value_name = "ADDRESS",
-        default_value = "127.0.0.1"
-    )]
-    address: String,
+    /// Address to listen to, for LiveSplitOne to connect to. [default: 127.0.0.1]
+    #[arg(short = 'a', long = "address", value_name = "ADDRESS")]
+    address: Option<String>,
 
-    /// Port to listen to, for LiveSplitOne to connect to.
-    #[arg(
-        short = 'p',
-        long = "port",
-        value_name = "PORT",
-        default_value = "51000"
-    )]
-    port: String,
+    /// Port to listen to, for LiveSplitOne to connect to. [default: 51000]
+    #[arg(short = 'p', long = "port", value_name = "PORT")]
+    port: Option<u16>,
 
-    /// Path to the folder containing the save data (files like 0.celeste).
+    /// Path to the folder containing the save data (files like 0.celeste). [default: ~/.local/share/Celeste/Saves/]
     #[arg(
         short = 'f',
         long = "save-location",
         value_name = "PATH",
-        default_value = "~/.local/share/Celeste/Saves/"
+        global = true
     )]
-    save_location: String,
+    save_location: Option<String>,
+
+    /// Stop autosplitting and mark the run as invalid when Assist Mode, Variant Mode or Debug Mode are turned on. Without Everest, Assist and Variant Mode are only seen once the game saved.
+    #[arg(long = "forbid-assists", overrides_with = "allow_assists")]
+    forbid_assists: bool,
+
+    /// Keep autosplitting when Assist Mode, Variant Mode or Debug Mode are turned on, even if the profile forbids them.
+    #[arg(long = "allow-assists", overrides_with = "forbid_assists")]
+    allow_assists: bool,
+
+    /// What to do when the game time goes backwards, after loading a savestate for example. [default: ignore]
+    #[arg(long = "on-time-regression", value_name = "POLICY")]
+    on_time_regression: Option<TimeRegressionPolicy>,
+
+    /// Timer to send the splits to. [default: livesplitone]
+    #[arg(long = "timer", value_name = "TIMER")]
+    timer: Option<TimerBackend>,
+
+    /// How many times per second the game's memory is read. [default: 1000]
+    #[arg(long = "polling-rate", value_name = "HZ")]
+    polling_rate: Option<u32>,
+
+    /// Replaces the "AutoReset" setting of the splits file.
+    #[arg(long = "auto-reset", value_name = "BOOL")]
+    auto_reset: Option<bool>,
+
+    /// Replaces the "SetGameTime" setting of the splits file.
+    #[arg(long = "set-game-time", value_name = "BOOL")]
+    set_game_time: Option<bool>,
+
+    /// Replaces the "FileTimeOffset" setting of the splits file.
+    #[arg(long = "file-time-offset", value_name = "BOOL")]
+    file_time_offset: Option<bool>,
+
+    /// How the run is started, reset and timed, instead of guessing it from the splits.
+    #[arg(long = "run-kind", value_name = "KIND")]
+    run_kind: Option<RunKind>,
+
+    /// Time the splits as an individual level, whatever they contain. Same as `--run-kind individual-level`.
+    #[arg(long = "il", conflicts_with_all = ["full_game", "run_kind"])]
+    il: bool,
+
+    /// Time the splits as a full game run, whatever they contain. Same as `--run-kind full-game`.
+    #[arg(long = "full-game", conflicts_with = "run_kind")]
+    full_game: bool,
+
+    /// File to write the time spent in each room to, when timing per room (CSV, or JSON if it ends with .json).
+    #[arg(long = "room-times", value_name = "PATH")]
+    room_times: Option<String>,
+
+    /// Write a line of JSON for every change of the game's state, split, reset and timer event, to `stdout` or to a Unix socket with `unix:PATH`.
+    #[arg(long = "events", value_name = "OUTPUT")]
+    events: Option<String>,
+
+    /// Serve the game's state on `http://ADDRESS/status`, and its changes on `/events`, for example on 127.0.0.1:8080.
+    #[arg(long = "http", value_name = "ADDRESS")]
+    http: Option<String>,
+
+    /// Folder to write text files for OBS to (room, berries, time...). Their format can be changed in the config file.
+    #[arg(long = "obs-dir", value_name = "PATH")]
+    obs_dir: Option<String>,
+
+    /// Read the game's memory through linsplit-helper, so that only the helper needs the permission to do it.
+    #[arg(long = "helper", value_name = "PATH")]
+    helper: Option<String>,
+
+    /// File to also write the logs to. It's rotated when it gets too big.
+    #[arg(long = "log-file", value_name = "PATH", global = true)]
+    log_file: Option<String>,
+
+    /// Log more details, like the split decisions (-v) or every read of the game (-vv). RUST_LOG filters like `memory_reader=debug` are also used.
+    #[arg(short = 'v', long = "verbose", action = clap::ArgAction::Count, global = true, conflicts_with = "quiet")]
+    verbose: u8,
+
+    /// Only log warnings (-q) or errors (-qq).
+    #[arg(short = 'q', long = "quiet", action = clap::ArgAction::Count, global = true)]
+    quiet: u8,
+
+    /// Profile of the config file to use. Command line arguments take precedence over it.
+    #[arg(short = 'P', long = "profile", value_name = "NAME", global = true)]
+    profile: Option<String>,
+
+    /// Path to the config file. [default: $XDG_CONFIG_HOME/linsplit/config.toml]
+    #[arg(short = 'c', long = "config", value_name = "PATH", global = true)]
+    config: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Check that LinSplit can find and read the game, and explain how to fix it otherwise
+    Doctor,
+    /// Look at the attempts recorded during the previous sessions
+    History {
+        #[command(subcommand)]
+        command: HistoryCommand,
+    },
+    /// List the splits the splits file can contain, with the areas, sides and checkpoints they use
+    ListSplits {
+        /// Print the list as JSON
+        #[arg(long = "json")]
+        json: bool,
+    },
+    /// Write a splits file for one of the usual categories
+    Generate {
+        /// Category of the run
+        #[arg(value_enum)]
+        category: generate::Category,
+        /// Chapter of an individual level, like ForsakenCity or 1
+        #[arg(long = "chapter", value_name = "AREA")]
+        chapter: Option<split_reader::Area>,
+        /// Side of an individual level: ASide, BSide or CSide
+        #[arg(long = "side", value_name = "SIDE", default_value = "ASide")]
+        side: split_reader::AreaMode,
+        /// Split at every checkpoint of an individual level
+        #[arg(long = "checkpoints")]
+        checkpoints: bool,
+        /// Where to write the splits, by default the category name with the .lss extension
+        #[arg(short = 'o', long = "output", value_name = "PATH")]
+        output: Option<PathBuf>,
+        /// Replace the file if it already exists
+        #[arg(long = "force")]
+        force: bool,
+    },
+    /// Run the splits against a recorded trace instead of the game, and show when each split fires
+    Simulate {
+        /// Trace recorded with `linsplit-helper -f SAVES > PATH`, a line of JSON per state of the game
+        #[arg(long = "trace", value_name = "PATH")]
+        trace: PathBuf,
+    },
+    /// Time attempts from one room to another of a chapter, without a splits file
+    Practice {
+        /// Chapter of the rooms, like ForsakenCity or 1
+        #[arg(long = "chapter", value_name = "AREA")]
+        chapter: split_reader::Area,
+        /// Side of the chapter: ASide, BSide or CSide
+        #[arg(long = "side", value_name = "SIDE", default_value = "ASide")]
+        side: split_reader::AreaMode,
+        /// Room starting an attempt when entered, like a-00
+        #[arg(long = "from", value_name = "ROOM")]
+        from: String,
+        /// Room ending an attempt when entered
+        #[arg(long = "to", value_name = "ROOM")]
+        to: String,
+        /// File to append the attempts to. [default: $XDG_DATA_HOME/linsplit/practice.jsonl]
+        #[arg(long = "attempts", value_name = "PATH")]
+        attempts: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum HistoryCommand {
+    /// List every recorded attempt
+    List,
+    /// Show the splits of an attempt
+    Show {
+        /// Id of the attempt, as shown by `history list`
+        id: usize,
+    },
+    /// Show the best segments, sum of best and resets of each split
+    Stats {
+        /// Splits file to show the statistics of. [default: the one of the last attempt]
+        splits: Option<String>,
+    },
+}
+
+impl Args {
+    /// The settings given on the command line, as a profile.
+    fn to_profile(&self) -> Profile {
+        Profile {
+            splits: self.splits.clone(),
+            timer: self.timer,
+            address: self.address.clone(),
+            port: self.port,
+            save_location: self.save_location.clone(),
+            polling_rate: self.polling_rate,
+            forbid_assists: if self.forbid_assists {
+                Some(true)
+            } else if self.allow_assists {
+                Some(false)
+            } else {
+                None
+            },
+            time_regression: self.on_time_regression,
+            room_times: self.room_times.clone(),
+            events: self.events.clone(),
+            http: self.http.clone(),
+            obs_dir: self.obs_dir.clone(),
+            obs_formats: Default::default(),
+            helper: self.helper.clone(),
+            log_file: self.log_file.clone(),
+            autosplitter: SettingsOverrides {
+                auto_reset: self.auto_reset,
+                set_game_time: self.set_game_time,
+                file_time_offset: self.file_time_offset,
+                run_kind: if self.il {
+                    Some(RunKind::IndividualLevel)
+                } else if self.full_game {
+                    Some(RunKind::FullGame)
+                } else {
+                    self.run_kind
+                },
+            },
+        }
+    }
 }
 
 #[cfg(target_os = "linux")]
 #[tokio::main]
-async fn main() {
+async fn main() -> Result<ExitCode> {
     let args = Args::parse();
 
-    let data = LinSplitData::new(
-        &args.splits,
-        &format!("{}:{}", args.address, args.port),
-        args.save_location,
-    )
-    .await;
-    data.main_loop().await;
+    if let Some(Commands::ListSplits { json }) = &args.command {
+        return list_splits::run(*json).map(|()| ExitCode::SUCCESS);
+    }
+    if let Some(Commands::Generate {
+        category,
+        chapter,
+        side,
+        checkpoints,
+        output,
+        force,
+    }) = &args.command
+    {
+        let request = generate::Request {
+            category: *category,
+            chapter: *chapter,
+            side: *side,
+            checkpoints: *checkpoints,
+            output: output.clone(),
+            force: *force,
+        };
+        return generate::run(&request).map(|()| ExitCode::SUCCESS);
+    }
+    if let Some(Commands::History { command }) = &args.command {
+        return match command {
+            HistoryCommand::List => history::list(),
+            HistoryCommand::Show { id } => history::show(*id),
+            HistoryCommand::Stats { splits } => {
+                // The attempts are recorded with the canonical path of their splits file
+                let splits = splits
+                    .as_deref()
+                    .map(expand_tilde)
+                    .transpose()?
+                    .map(|path| std::fs::canonicalize(&path).unwrap_or(path.into_owned()));
+                history::stats(splits.as_deref().and_then(|path| path.to_str()))
+            }
+        }
+        .map(|()| ExitCode::SUCCESS);
+    }
+
+    let config = ConfigFile::load(args.config.as_deref())?;
+    let profile = args
+        .to_profile()
+        .or(config.profile(args.profile.as_deref())?);
+    let log_file = profile.log_file.as_deref().map(expand_tilde).transpose()?;
+    logging::init(
+        args.verbose.min(2) as i8 - args.quiet.min(2) as i8,
+        log_file.as_deref(),
+    )?;
+    if let Some(Commands::Doctor) = &args.command {
+        return doctor::run(&profile).await.map(|()| ExitCode::SUCCESS);
+    }
+    if let Some(Commands::Practice {
+        chapter,
+        side,
+        from,
+        to,
+        attempts,
+    }) = &args.command
+    {
+        let segment = practice::Segment {
+            area: *chapter,
+            side: *side,
+            from: from.clone(),
+            to: to.clone(),
+        };
+        let path = attempts
+            .as_deref()
+            .map(expand_tilde)
+            .transpose()?
+            .map(|path| path.into_owned());
+        let reader = profile.reader()?;
+        let result = tokio::select! {
+            result = practice::run(segment, path, &reader) => result,
+            signal = stop_signal() => signal.map(|signal| info!("Received {signal}, stopping...")),
+        };
+        log::logger().flush();
+        return result.map(|()| ExitCode::SUCCESS);
+    }
+    let options = profile.resolve()?;
+    if let Some(Commands::Simulate { trace }) = &args.command {
+        // Splits that never fire make the simulation fail, for scripts checking splits files
+        return Ok(match simulate::run(&options, trace).await? {
+            true => ExitCode::SUCCESS,
+            false => ExitCode::FAILURE,
+        });
+    }
+
+    // Listening from the start, as waiting for LiveSplit One and Celeste can take a while
+    let stop = stop_signal();
+    tokio::pin!(stop);
+    let data = tokio::select! {
+        data = LinSplitData::new(&options) => data?,
+        signal = &mut stop => {
+            info!("Received {}, stopping...", signal?);
+            log::logger().flush();
+            return Ok(ExitCode::SUCCESS);
+        }
+    };
+    // An error stops with the exit code 1, a signal with 0 once everything is cleaned up
+    let result = tokio::select! {
+        result = data.main_loop() => result,
+        signal = &mut stop => signal.map(|signal| info!("Received {signal}, stopping...")),
+    };
+    data.shutdown().await;
+    let exit_code = match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            error!("{error}");
+            ExitCode::FAILURE
+        }
+    };
+    log::logger().flush();
+    Ok(exit_code)
+}
+
+/// Waits until LinSplit is asked to stop, and returns the name of the signal.
+async fn stop_signal() -> Result<&'static str> {
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    Ok(tokio::select! {
+        _ = interrupt.recv() => "SIGINT",
+        _ = terminate.recv() => "SIGTERM",
+    })
 }
 
 #[cfg(not(target_os = "linux"))]
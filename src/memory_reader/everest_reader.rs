@@ -1,6 +1,6 @@
+use crate::area::{Area, AreaMode};
 use crate::memory_reader::flags::{AutoSplitterChapterFlags, AutoSplitterFileFlags};
 use crate::memory_reader::mem_reader::MemReader;
-use crate::split_reader::{Area, AreaMode};
 use anyhow::{Result, anyhow};
 use procfs::process::{MMPermissions, Process};
 use std::{
@@ -12,51 +12,122 @@ use std::{
 pub(super) struct EverestMemReader {
     memory: File,
     offset: u64,
+    layout: &'static InfoLayout,
+}
+
+/// Offsets of the fields of Everest's core autosplitter info, relative to its magic.
+struct InfoLayout {
+    version: u8,
+    chapter_sid: u64,
+    area_id: u64,
+    area_difficulty: u64,
+    level_name: u64,
+    level_time: u64,
+    chapter_strawberries: u64,
+    chapter_flags: u64,
+    game_time: u64,
+    strawberries: u64,
+    cassettes: u64,
+    heart_gems: u64,
+    file_flags: u64,
+}
+
+/// Layouts of the versions of the info, oldest first.
+const INFO_LAYOUTS: &[InfoLayout] = &[InfoLayout {
+    version: 3,
+    chapter_sid: 0x28,
+    area_id: 0x30,
+    area_difficulty: 0x34,
+    level_name: 0x38,
+    level_time: 0x40,
+    chapter_strawberries: 0x48,
+    chapter_flags: 0x4c,
+    game_time: 0x50,
+    strawberries: 0x58,
+    cassettes: 0x60,
+    heart_gems: 0x64,
+    file_flags: 0x68,
+}];
+
+impl InfoLayout {
+    /// The layout of the info of version `version`. Other versions can move any field, so
+    /// they aren't read at all.
+    fn for_version(version: u8) -> Result<&'static Self> {
+        INFO_LAYOUTS
+            .iter()
+            .find(|layout| layout.version == version)
+            .ok_or_else(|| {
+                let known: Vec<String> = INFO_LAYOUTS
+                    .iter()
+                    .map(|layout| layout.version.to_string())
+                    .collect();
+                anyhow!(
+                    "Everest's autosplitter info has version {version}, but linsplit can only read version {}. Try updating linsplit or Everest.",
+                    known.join(", ")
+                )
+            })
+    }
 }
 
 impl EverestMemReader {
+    /// Waits until the Everest autosplitter info is found in a Celeste process.
     pub async fn new() -> Result<Option<Box<Self>>> {
-        const CORE_AUTOSPLITTER_MAGIC: &[u8] = b"EVERESTAUTOSPLIT\xF0\xF1\xF2\xF3";
-        const CORE_AUTOSPLITTER_INFO_MIN_VERSION: u8 = 3;
         loop {
-            let all_processes: Vec<Process> = procfs::process::all_processes()
-                .expect("Can't read /proc")
-                .filter_map(|p| match p {
-                    Ok(p) => {
-                        if p.stat().ok()?.comm.contains("Celeste") {
-                            return Some(p);
-                        }
-                        None
+            if let Some(reader) = Self::scan()? {
+                return Ok(Some(reader));
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    }
+
+    /// Looks for the Everest autosplitter info in the running Celeste processes, once.
+    pub fn scan() -> Result<Option<Box<Self>>> {
+        const CORE_AUTOSPLITTER_MAGIC: &[u8] = b"EVERESTAUTOSPLIT\xF0\xF1\xF2\xF3";
+        let all_processes: Vec<Process> = procfs::process::all_processes()
+            .expect("Can't read /proc")
+            .filter_map(|p| match p {
+                Ok(p) => {
+                    if p.stat().ok()?.comm.contains("Celeste") {
+                        return Some(p);
                     }
-                    Err(_) => None,
-                })
-                .collect();
-            for process in all_processes {
-                if let Ok(mut memory) = process.mem()
-                    && let Ok(maps) = process.maps()
-                {
-                    for map in maps {
-                        if map.perms.contains(MMPermissions::READ) {
-                            memory.seek(SeekFrom::Start(map.address.0))?;
-                            let mut buf: [u8; 20] = [0u8; 20];
-                            memory.read_exact(&mut buf).unwrap_or(());
-                            if buf.iter().eq(CORE_AUTOSPLITTER_MAGIC) {
-                                let mut buf2: [u8; 1] = [0];
-                                memory.seek(SeekFrom::Current(0x03))?;
-                                memory.read_exact(&mut buf2).unwrap_or(());
-                                if u8::from_be_bytes(buf2) < CORE_AUTOSPLITTER_INFO_MIN_VERSION {
-                                    continue;
+                    None
+                }
+                Err(_) => None,
+            })
+            .collect();
+        let mut unsupported = None;
+        for process in all_processes {
+            if let Ok(mut memory) = process.mem()
+                && let Ok(maps) = process.maps()
+            {
+                for map in maps {
+                    if map.perms.contains(MMPermissions::READ) {
+                        memory.seek(SeekFrom::Start(map.address.0))?;
+                        let mut buf: [u8; 20] = [0u8; 20];
+                        memory.read_exact(&mut buf).unwrap_or(());
+                        if buf.iter().eq(CORE_AUTOSPLITTER_MAGIC) {
+                            let mut buf2: [u8; 1] = [0];
+                            memory.seek(SeekFrom::Current(0x03))?;
+                            memory.read_exact(&mut buf2).unwrap_or(());
+                            match InfoLayout::for_version(u8::from_be_bytes(buf2)) {
+                                Ok(layout) => {
+                                    return Ok(Some(Box::new(Self {
+                                        memory,
+                                        offset: map.address.0,
+                                        layout,
+                                    })));
                                 }
-                                return Ok(Some(Box::new(Self {
-                                    memory,
-                                    offset: map.address.0,
-                                })));
+                                // Keep looking in case this is a stale copy of the magic
+                                Err(error) => unsupported = Some(error),
                             }
                         }
                     }
                 }
             }
-            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+        match unsupported {
+            Some(error) => Err(error),
+            None => Ok(None),
         }
     }
 
@@ -80,86 +151,129 @@ impl EverestMemReader {
         self.memory.read_exact(&mut buf)?;
         Ok(buf)
     }
+
+    /// Reads a string the info points to at `offset`: UTF-8 bytes preceded by their length.
+    fn read_string(&mut self, offset: u64) -> Result<String> {
+        let string_ptr = u64::from_le_bytes(self.read_bits(offset)?);
+        if string_ptr == 0 {
+            return Ok(String::new());
+        }
+        if string_ptr < 2 {
+            return Err(anyhow!("invalid string pointer"));
+        }
+        let len = u16::from_le_bytes(self.read_global_bits(string_ptr - 2)?);
+        Ok(String::from_utf8(
+            self.read_vec_global_bits(string_ptr, len as usize)?,
+        )?)
+    }
+
+    /// The bits this version of LinSplit doesn't know, like the flags a newer Everest adds, are
+    /// dropped rather than failing every read.
+    fn chapter_flags(&mut self) -> Result<AutoSplitterChapterFlags> {
+        Ok(AutoSplitterChapterFlags::from_bits_truncate(
+            u32::from_le_bytes(self.read_bits(self.layout.chapter_flags)?),
+        ))
+    }
+
+    /// Unknown bits are dropped, like for the chapter flags.
+    fn file_flags(&mut self) -> Result<AutoSplitterFileFlags> {
+        Ok(AutoSplitterFileFlags::from_bits_truncate(
+            u32::from_le_bytes(self.read_bits(self.layout.file_flags)?),
+        ))
+    }
 }
 
 impl MemReader for EverestMemReader {
     fn chapter_complete(&mut self) -> Result<bool> {
-        Ok(
-            AutoSplitterChapterFlags::from_bits(u32::from_le_bytes(self.read_bits(0x4c)?))
-                .ok_or(anyhow!("a"))?
-                .contains(AutoSplitterChapterFlags::CHAPTER_COMPLETE),
-        )
+        Ok(self
+            .chapter_flags()?
+            .contains(AutoSplitterChapterFlags::CHAPTER_COMPLETE))
     }
 
     fn level_name(&mut self) -> Result<String> {
-        let level_name_str = u64::from_le_bytes(self.read_bits(0x38)?);
-        if level_name_str < 2 {
-            return Err(anyhow!("failed to get level_name_str"));
-        }
-        let name_len = u16::from_le_bytes(self.read_global_bits(level_name_str - 2)?);
-        Ok(String::from_utf8(self.read_vec_global_bits(
-            level_name_str,
-            name_len as usize,
-        )?)?)
+        // An empty name means no level is loaded, e.g. in the menus
+        self.read_string(self.layout.level_name)
     }
 
     fn area_id(&mut self) -> Result<Area> {
-        Area::try_from(i32::from_le_bytes(self.read_bits(0x30)?))
+        Area::try_from(i32::from_le_bytes(self.read_bits(self.layout.area_id)?))
     }
 
     fn area_difficulty(&mut self) -> Result<AreaMode> {
-        AreaMode::try_from(i32::from_le_bytes(self.read_bits(0x34)?))
+        AreaMode::try_from(i32::from_le_bytes(
+            self.read_bits(self.layout.area_difficulty)?,
+        ))
     }
 
     fn chapter_started(&mut self) -> Result<bool> {
-        Ok(
-            AutoSplitterChapterFlags::from_bits(u32::from_le_bytes(self.read_bits(0x4c)?))
-                .ok_or(anyhow!("failed"))?
-                .contains(AutoSplitterChapterFlags::CHAPTER_STARTED),
-        )
+        Ok(self
+            .chapter_flags()?
+            .contains(AutoSplitterChapterFlags::CHAPTER_STARTED))
     }
 
     fn game_time(&mut self) -> Result<f64> {
-        Ok(i64::from_le_bytes(self.read_bits(0x50)?) as f64 / 10000000.)
+        Ok(i64::from_le_bytes(self.read_bits(self.layout.game_time)?) as f64 / 10000000.)
     }
 
     fn level_time(&mut self) -> Result<f64> {
-        Ok(i64::from_le_bytes(self.read_bits(0x40)?) as f64 / 10000000.)
+        Ok(i64::from_le_bytes(self.read_bits(self.layout.level_time)?) as f64 / 10000000.)
     }
 
     fn strawberries(&mut self) -> Result<u32> {
-        Ok(u32::from_le_bytes(self.read_bits(0x58)?))
+        Ok(u32::from_le_bytes(
+            self.read_bits(self.layout.strawberries)?,
+        ))
     }
 
     fn cassettes(&mut self) -> Result<u32> {
-        Ok(u32::from_le_bytes(self.read_bits(0x60)?))
+        Ok(u32::from_le_bytes(self.read_bits(self.layout.cassettes)?))
     }
 
     fn chapter_cassette_collected(&mut self) -> Result<bool> {
-        Ok(
-            AutoSplitterChapterFlags::from_bits(u32::from_le_bytes(self.read_bits(0x4c)?))
-                .ok_or(anyhow!("failed"))?
-                .contains(AutoSplitterChapterFlags::CHAPTER_CASSETTE),
-        )
+        Ok(self
+            .chapter_flags()?
+            .contains(AutoSplitterChapterFlags::CHAPTER_CASSETTE))
     }
 
     fn heart_gems(&mut self) -> Result<u32> {
-        Ok(u32::from_le_bytes(self.read_bits(0x64)?))
+        Ok(u32::from_le_bytes(self.read_bits(self.layout.heart_gems)?))
     }
 
     fn chapter_heart_collected(&mut self) -> Result<bool> {
-        Ok(
-            AutoSplitterChapterFlags::from_bits(u32::from_le_bytes(self.read_bits(0x4c)?))
-                .ok_or(anyhow!("failed"))?
-                .contains(AutoSplitterChapterFlags::CHAPTER_HEART),
-        )
+        Ok(self
+            .chapter_flags()?
+            .contains(AutoSplitterChapterFlags::CHAPTER_HEART))
     }
 
     fn starting_new_file(&mut self) -> Result<bool> {
-        Ok(
-            AutoSplitterFileFlags::from_bits(u32::from_le_bytes(self.read_bits(0x68)?))
-                .ok_or(anyhow!("failed"))?
-                .contains(AutoSplitterFileFlags::STARTING_NEW_FILE),
-        )
+        Ok(self
+            .file_flags()?
+            .contains(AutoSplitterFileFlags::STARTING_NEW_FILE))
+    }
+
+    fn is_debug(&mut self) -> Result<bool> {
+        Ok(self.file_flags()?.contains(AutoSplitterFileFlags::IS_DEBUG))
+    }
+
+    fn assist_mode(&mut self) -> Result<bool> {
+        Ok(self
+            .file_flags()?
+            .contains(AutoSplitterFileFlags::ASSIST_MODE))
+    }
+
+    fn variants_mode(&mut self) -> Result<bool> {
+        Ok(self
+            .file_flags()?
+            .contains(AutoSplitterFileFlags::VARIANTS_MODE))
+    }
+
+    fn chapter_sid(&mut self) -> Result<String> {
+        self.read_string(self.layout.chapter_sid)
+    }
+
+    fn chapter_strawberries(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(
+            self.read_bits(self.layout.chapter_strawberries)?,
+        ))
     }
 }
@@ -1,15 +1,27 @@
-use std::time::Duration;
+use anyhow::Result;
+use log::{info, warn};
+use std::fmt;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
+use strum_macros::Display;
 use tokio::select;
 
-use crate::split_reader::{Area, AreaMode};
+use crate::area::{Area, AreaMode};
 
 use super::everest_reader::EverestMemReader;
+use super::helper_reader::{HelperCommand, HelperMemReader};
 use super::mem_reader::MemReader;
+use super::trace_reader::TraceMemReader;
 use super::vanilla_reader::VanillaMemReader;
 
-pub struct GameData {
-    mem_reader: Box<dyn MemReader>,
+/// How long the updates can keep failing before the reader is considered lost, whatever the
+/// polling rate.
+const MAX_FAILURE_DURATION: Duration = Duration::from_millis(500);
+
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct GameState {
     pub chapter_complete: bool,
     pub level_name: String,
     pub area_id: Area,
@@ -23,71 +35,277 @@ pub struct GameData {
     pub heart_gems: u32,
     pub chapter_heart_collected: bool,
     pub starting_new_file: bool,
+    pub is_debug: bool,
+    /// Unknown when the reader can't tell, like when vanilla's save file can't be read.
+    pub assist_mode: Option<bool>,
+    pub variants_mode: Option<bool>,
+    pub chapter_sid: Option<String>,
+    pub chapter_strawberries: Option<u32>,
+}
+
+/// A field of [`GameState`], used to report which reads failed.
+#[derive(Clone, Copy, Debug, PartialEq, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum Field {
+    /// The whole state, for readers that read it at once.
+    Snapshot,
+    ChapterComplete,
+    LevelName,
+    AreaId,
+    AreaDifficulty,
+    ChapterStarted,
+    GameTime,
+    LevelTime,
+    Strawberries,
+    Cassettes,
+    ChapterCassetteCollected,
+    HeartGems,
+    ChapterHeartCollected,
+    StartingNewFile,
+    IsDebug,
+}
+
+#[derive(Debug)]
+pub struct UpdateError {
+    pub failed: Vec<(Field, anyhow::Error)>,
+    pub consecutive_failures: u32,
+}
+
+/// A way of reading Celeste's memory, found by [`GameData::search_again`].
+pub struct Reader(Box<dyn MemReader>);
+
+pub struct GameData {
+    mem_reader: Box<dyn MemReader>,
+    save_location: String,
+    helper: Option<HelperCommand>,
+    consecutive_failures: u32,
+    /// When the updates started failing, while they do.
+    failing_since: Option<Instant>,
+    /// Whether the states come from a recorded trace instead of the game.
+    replaying: bool,
+    pub state: GameState,
 }
 
 impl GameData {
-    pub async fn new(save_location: String) -> Self {
-        println!("Waiting for Celeste...");
-        let mem_reader: Box<dyn MemReader>;
+    /// Waits for Celeste, reading its memory directly or through `helper` if given.
+    pub async fn new(save_location: String, helper: Option<HelperCommand>) -> Self {
+        Self {
+            mem_reader: Self::find_reader(&save_location, helper.as_ref()).await,
+            save_location,
+            helper,
+            consecutive_failures: 0,
+            failing_since: None,
+            replaying: false,
+            state: GameState::default(),
+        }
+    }
+
+    /// Reads the states recorded in the trace at `path` instead of the game, one per update.
+    pub fn replay(path: &Path) -> Result<Self> {
+        Ok(Self {
+            mem_reader: TraceMemReader::open(path)?,
+            save_location: String::new(),
+            helper: None,
+            consecutive_failures: 0,
+            failing_since: None,
+            replaying: true,
+            state: GameState::default(),
+        })
+    }
+
+    async fn find_reader(
+        save_location: &str,
+        helper: Option<&HelperCommand>,
+    ) -> Box<dyn MemReader> {
+        if let Some(helper) = helper {
+            loop {
+                match HelperMemReader::new(helper).await {
+                    Ok(reader) => {
+                        info!("Reading Celeste through {}.", helper.path.display());
+                        return reader;
+                    }
+                    Err(error) => warn!("{error}, restarting it."),
+                }
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        }
+        info!("Waiting for Celeste...");
+        let mut warned = false;
         loop {
             select! {
-                res = VanillaMemReader::new(save_location.clone()) => {
+                res = VanillaMemReader::new(save_location.to_string()) => {
                     if let Ok(Some(reader)) = res {
-                        println!("Found Vanilla Celeste.");
-                        mem_reader = reader;
-                        break
+                        info!("Found Vanilla Celeste.");
+                        return reader;
                     }
                 }
                 res = EverestMemReader::new() => {
-                    if let Ok(Some(reader)) = res {
-                        println!("Found Everest.");
-                        mem_reader = reader;
-                        break;
+                    match res {
+                        Ok(Some(reader)) => {
+                            info!("Found Everest.");
+                            return reader;
+                        }
+                        Err(error) => {
+                            if !warned {
+                                warn!("{error}");
+                                warned = true;
+                            }
+                            tokio::time::sleep(Duration::from_secs(5)).await;
+                        }
+                        Ok(None) => {}
                     }
                 }
                 _ = tokio::time::sleep(Duration::from_secs(5)) => {}
             }
         }
-        Self {
-            mem_reader,
-            chapter_complete: false,
-            level_name: String::new(),
-            area_id: Area::Unknown,
-            area_difficulty: AreaMode::Unknown,
-            chapter_started: false,
-            game_time: 0.0,
-            level_time: 0.0,
-            strawberries: 0,
-            cassettes: 0,
-            chapter_cassette_collected: false,
-            heart_gems: 0,
-            chapter_heart_collected: false,
-            starting_new_file: false,
+    }
+
+    /// Looks for Celeste once with each reader, without waiting for it: whether the vanilla
+    /// and the Everest readers found it.
+    pub async fn probe_readers(save_location: &str) -> (Result<bool>, Result<bool>) {
+        let vanilla = VanillaMemReader::new(save_location.to_string())
+            .await
+            .map(|reader| reader.is_some());
+        let everest = EverestMemReader::scan().map(|reader| reader.is_some());
+        (vanilla, everest)
+    }
+
+    /// Drops the current reader and waits for Celeste to be found again, e.g. after a restart
+    /// of the game. The last known state is kept until the next successful update.
+    pub async fn reattach(&mut self) {
+        let reader = self.search_again().await;
+        self.attach(reader);
+    }
+
+    /// Waits for Celeste to be found again, like [`GameData::reattach`], without borrowing
+    /// the game data: its last state can still be read while the game is gone.
+    pub fn search_again(&self) -> impl Future<Output = Reader> + Send + 'static {
+        let save_location = self.save_location.clone();
+        let helper = self.helper.clone();
+        async move { Reader(Self::find_reader(&save_location, helper.as_ref()).await) }
+    }
+
+    /// Replaces the current reader with one found by [`GameData::search_again`].
+    pub fn attach(&mut self, reader: Reader) {
+        self.mem_reader = reader.0;
+        self.consecutive_failures = 0;
+        self.failing_since = None;
+    }
+
+    /// Whether updates have been failing for long enough that the reader should be replaced.
+    pub fn should_reattach(&self) -> bool {
+        !self.replaying
+            && self
+                .failing_since
+                .is_some_and(|since| since.elapsed() >= MAX_FAILURE_DURATION)
+    }
+
+    /// Whether there's no state left to read, at the end of a replayed trace.
+    pub fn ended(&self) -> bool {
+        self.mem_reader.ended()
+    }
+
+    /// Reads a new state from the game. If any field can't be read, the previous state is
+    /// kept and the failing fields are reported.
+    pub fn update(&mut self) -> Result<(), UpdateError> {
+        let mut failed = vec![];
+        let reader = &mut self.mem_reader;
+        if let Err(error) = reader.refresh() {
+            failed.push((Field::Snapshot, error));
         }
+        let state = GameState {
+            starting_new_file: read(
+                &mut failed,
+                Field::StartingNewFile,
+                reader.starting_new_file(),
+            ),
+            chapter_complete: read(
+                &mut failed,
+                Field::ChapterComplete,
+                reader.chapter_complete(),
+            ),
+            level_name: read(&mut failed, Field::LevelName, reader.level_name()),
+            area_id: read(&mut failed, Field::AreaId, reader.area_id()),
+            area_difficulty: read(&mut failed, Field::AreaDifficulty, reader.area_difficulty()),
+            chapter_started: read(&mut failed, Field::ChapterStarted, reader.chapter_started()),
+            game_time: read(&mut failed, Field::GameTime, reader.game_time()),
+            level_time: read(&mut failed, Field::LevelTime, reader.level_time()),
+            strawberries: read(&mut failed, Field::Strawberries, reader.strawberries()),
+            cassettes: read(&mut failed, Field::Cassettes, reader.cassettes()),
+            chapter_cassette_collected: read(
+                &mut failed,
+                Field::ChapterCassetteCollected,
+                reader.chapter_cassette_collected(),
+            ),
+            heart_gems: read(&mut failed, Field::HeartGems, reader.heart_gems()),
+            chapter_heart_collected: read(
+                &mut failed,
+                Field::ChapterHeartCollected,
+                reader.chapter_heart_collected(),
+            ),
+            is_debug: read(&mut failed, Field::IsDebug, reader.is_debug()),
+            // Optional fields don't make the update fail, as some readers can't provide them
+            assist_mode: reader.assist_mode().ok(),
+            variants_mode: reader.variants_mode().ok(),
+            chapter_sid: reader.chapter_sid().ok(),
+            chapter_strawberries: reader.chapter_strawberries().ok(),
+        };
+        if !failed.is_empty() {
+            self.consecutive_failures += 1;
+            self.failing_since.get_or_insert_with(Instant::now);
+            return Err(UpdateError {
+                failed,
+                consecutive_failures: self.consecutive_failures,
+            });
+        }
+        self.consecutive_failures = 0;
+        self.failing_since = None;
+
+        let last_forbidden_modes = self.state.forbidden_modes();
+        for mode in state.forbidden_modes() {
+            if !last_forbidden_modes.contains(&mode) {
+                warn!("{mode} was turned on, this run won't be valid for leaderboards.");
+            }
+        }
+        self.state = state;
+        Ok(())
+    }
+}
+
+impl GameState {
+    /// Names of the modes currently enabled that leaderboard rules don't allow.
+    pub fn forbidden_modes(&self) -> Vec<&'static str> {
+        [
+            (self.is_debug, "Debug Mode"),
+            (self.assist_mode == Some(true), "Assist Mode"),
+            (self.variants_mode == Some(true), "Variant Mode"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .collect()
     }
+}
+
+fn read<T: Default>(
+    failed: &mut Vec<(Field, anyhow::Error)>,
+    field: Field,
+    value: anyhow::Result<T>,
+) -> T {
+    value.unwrap_or_else(|error| {
+        failed.push((field, error));
+        T::default()
+    })
+}
 
-    pub fn update(&mut self) {
-        self.starting_new_file = self.mem_reader.starting_new_file().unwrap_or(false);
-        self.chapter_complete = self.mem_reader.chapter_complete().unwrap_or(false);
-        self.level_name = self
-            .mem_reader
-            .level_name()
-            .unwrap_or(String::from("Unknown"));
-        self.area_id = self.mem_reader.area_id().unwrap_or(Area::Unknown);
-        self.area_difficulty = self
-            .mem_reader
-            .area_difficulty()
-            .unwrap_or(AreaMode::Unknown);
-        self.chapter_started = self.mem_reader.chapter_started().unwrap_or(false);
-        self.game_time = self.mem_reader.game_time().unwrap_or(0.0);
-        self.level_time = self.mem_reader.level_time().unwrap_or(0.0);
-        self.strawberries = self.mem_reader.strawberries().unwrap_or(0);
-        self.cassettes = self.mem_reader.cassettes().unwrap_or(0);
-        self.chapter_cassette_collected = self
-            .mem_reader
-            .chapter_cassette_collected()
-            .unwrap_or(false);
-        self.heart_gems = self.mem_reader.heart_gems().unwrap_or(0);
-        self.chapter_heart_collected = self.mem_reader.chapter_heart_collected().unwrap_or(false);
+impl fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to read")?;
+        for (i, (field, error)) in self.failed.iter().enumerate() {
+            let sep = if i == 0 { " " } else { ", " };
+            write!(f, "{sep}{field} ({error})")?;
+        }
+        Ok(())
     }
 }
+
+impl std::error::Error for UpdateError {}
@@ -0,0 +1,124 @@
+use anyhow::{Result, anyhow};
+use log::info;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::memory_reader::game_data::GameState;
+use crate::memory_reader::mem_reader::SnapshotReader;
+
+/// A line written by `linsplit-helper` on its standard output.
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum HelperMessage {
+    /// The state of the game was read.
+    State(GameState),
+    /// The state of the game couldn't be read.
+    Error(String),
+}
+
+/// How to start `linsplit-helper`.
+#[derive(Clone, Debug)]
+pub struct HelperCommand {
+    pub path: PathBuf,
+    pub save_location: String,
+    pub polling_interval: Duration,
+}
+
+/// What the helper last sent.
+#[derive(Default)]
+struct Received {
+    message: Option<HelperMessage>,
+    exited: bool,
+}
+
+/// Reads the game's state from `linsplit-helper`, a small executable that is the only one
+/// needing the permission to read the game's memory.
+pub(super) struct HelperMemReader {
+    child: Child,
+    received: Arc<Mutex<Received>>,
+    state: GameState,
+}
+
+impl HelperMemReader {
+    /// Starts the helper and waits until it found the game.
+    pub async fn new(command: &HelperCommand) -> Result<Box<Self>> {
+        let polling_rate = (1. / command.polling_interval.as_secs_f64())
+            .round()
+            .max(1.);
+        let mut child = Command::new(&command.path)
+            .arg("--save-location")
+            .arg(&command.save_location)
+            .arg("--polling-rate")
+            .arg(polling_rate.to_string())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| anyhow!("couldn't start {}: {e}", command.path.display()))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or(anyhow!("no output from the helper"))?;
+        let received = Arc::new(Mutex::new(Received::default()));
+        let thread_received = Arc::clone(&received);
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                match serde_json::from_str::<HelperMessage>(&line) {
+                    Ok(message) => thread_received.lock().unwrap().message = Some(message),
+                    // Messages of the helper itself, like "Waiting for Celeste..."
+                    Err(_) => info!("{line}"),
+                }
+            }
+            thread_received.lock().unwrap().exited = true;
+        });
+        loop {
+            {
+                let received = received.lock().unwrap();
+                if received.exited {
+                    return Err(anyhow!("the helper stopped"));
+                }
+                if let Some(HelperMessage::State(_)) = received.message {
+                    break;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        Ok(Box::new(HelperMemReader {
+            child,
+            received,
+            state: GameState::default(),
+        }))
+    }
+}
+
+impl Drop for HelperMemReader {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl SnapshotReader for HelperMemReader {
+    fn read_snapshot(&mut self) -> Result<()> {
+        let received = self.received.lock().unwrap();
+        if received.exited {
+            return Err(anyhow!("the helper stopped"));
+        }
+        match &received.message {
+            Some(HelperMessage::State(state)) => {
+                self.state = state.clone();
+                Ok(())
+            }
+            Some(HelperMessage::Error(error)) => Err(anyhow!("{error}")),
+            None => Err(anyhow!("nothing received from the helper yet")),
+        }
+    }
+
+    fn snapshot(&self) -> &GameState {
+        &self.state
+    }
+}
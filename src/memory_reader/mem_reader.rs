@@ -1,8 +1,20 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 
-use crate::split_reader::{Area, AreaMode};
+use crate::area::{Area, AreaMode};
+use crate::memory_reader::game_data::GameState;
 
 pub trait MemReader: Send + Sync {
+    /// Called before reading the fields of a new state, for readers that read the whole
+    /// state at once.
+    fn refresh(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Whether the reader won't read any new state, like at the end of a recorded trace.
+    fn ended(&self) -> bool {
+        false
+    }
+
     fn chapter_complete(&mut self) -> Result<bool>;
     fn level_name(&mut self) -> Result<String>;
     fn area_id(&mut self) -> Result<Area>;
@@ -16,4 +28,122 @@ pub trait MemReader: Send + Sync {
     fn heart_gems(&mut self) -> Result<u32>;
     fn chapter_heart_collected(&mut self) -> Result<bool>;
     fn starting_new_file(&mut self) -> Result<bool>;
+    fn is_debug(&mut self) -> Result<bool>;
+
+    // Fields that not every reader can provide
+    fn assist_mode(&mut self) -> Result<bool> {
+        Err(anyhow!("assist mode not supported by this reader"))
+    }
+    fn variants_mode(&mut self) -> Result<bool> {
+        Err(anyhow!("variants mode not supported by this reader"))
+    }
+    fn chapter_sid(&mut self) -> Result<String> {
+        Err(anyhow!("chapter SID not supported by this reader"))
+    }
+    fn chapter_strawberries(&mut self) -> Result<u32> {
+        Err(anyhow!("chapter strawberries not supported by this reader"))
+    }
+}
+
+/// A reader that gets whole states at once, from `linsplit-helper` or from a recorded trace.
+pub trait SnapshotReader: Send + Sync {
+    /// Gets a new state, called by [`MemReader::refresh`].
+    fn read_snapshot(&mut self) -> Result<()>;
+    /// The last state that was read.
+    fn snapshot(&self) -> &GameState;
+
+    fn ended(&self) -> bool {
+        false
+    }
+}
+
+impl<T: SnapshotReader> MemReader for T {
+    fn refresh(&mut self) -> Result<()> {
+        self.read_snapshot()
+    }
+
+    fn ended(&self) -> bool {
+        SnapshotReader::ended(self)
+    }
+
+    fn chapter_complete(&mut self) -> Result<bool> {
+        Ok(self.snapshot().chapter_complete)
+    }
+
+    fn level_name(&mut self) -> Result<String> {
+        Ok(self.snapshot().level_name.clone())
+    }
+
+    fn area_id(&mut self) -> Result<Area> {
+        Ok(self.snapshot().area_id)
+    }
+
+    fn area_difficulty(&mut self) -> Result<AreaMode> {
+        Ok(self.snapshot().area_difficulty)
+    }
+
+    fn chapter_started(&mut self) -> Result<bool> {
+        Ok(self.snapshot().chapter_started)
+    }
+
+    fn game_time(&mut self) -> Result<f64> {
+        Ok(self.snapshot().game_time)
+    }
+
+    fn level_time(&mut self) -> Result<f64> {
+        Ok(self.snapshot().level_time)
+    }
+
+    fn strawberries(&mut self) -> Result<u32> {
+        Ok(self.snapshot().strawberries)
+    }
+
+    fn cassettes(&mut self) -> Result<u32> {
+        Ok(self.snapshot().cassettes)
+    }
+
+    fn chapter_cassette_collected(&mut self) -> Result<bool> {
+        Ok(self.snapshot().chapter_cassette_collected)
+    }
+
+    fn heart_gems(&mut self) -> Result<u32> {
+        Ok(self.snapshot().heart_gems)
+    }
+
+    fn chapter_heart_collected(&mut self) -> Result<bool> {
+        Ok(self.snapshot().chapter_heart_collected)
+    }
+
+    fn starting_new_file(&mut self) -> Result<bool> {
+        Ok(self.snapshot().starting_new_file)
+    }
+
+    fn is_debug(&mut self) -> Result<bool> {
+        Ok(self.snapshot().is_debug)
+    }
+
+    fn assist_mode(&mut self) -> Result<bool> {
+        self.snapshot()
+            .assist_mode
+            .ok_or(anyhow!("assist mode not in the state"))
+    }
+
+    fn variants_mode(&mut self) -> Result<bool> {
+        self.snapshot()
+            .variants_mode
+            .ok_or(anyhow!("variants mode not in the state"))
+    }
+
+    fn chapter_sid(&mut self) -> Result<String> {
+        self.snapshot()
+            .chapter_sid
+            .clone()
+            .ok_or(anyhow!("chapter SID not in the state"))
+    }
+
+    fn chapter_strawberries(&mut self) -> Result<u32> {
+        self.snapshot()
+            .chapter_strawberries
+            .ok_or(anyhow!("chapter strawberries not in the state"))
+    }
 }
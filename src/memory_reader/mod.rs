@@ -1,5 +1,7 @@
-pub mod everest_reader;
+mod everest_reader;
 mod flags;
 pub mod game_data;
+pub mod helper_reader;
 mod mem_reader;
-pub mod vanilla_reader;
+mod trace_reader;
+mod vanilla_reader;
@@ -0,0 +1,70 @@
+use anyhow::{Result, anyhow};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines};
+use std::path::{Path, PathBuf};
+
+use crate::memory_reader::game_data::GameState;
+use crate::memory_reader::helper_reader::HelperMessage;
+use crate::memory_reader::mem_reader::SnapshotReader;
+
+/// Replays a recorded trace, one state per update. A trace is what `linsplit-helper` writes
+/// on its standard output: a line of JSON per state of the game.
+pub(super) struct TraceMemReader {
+    path: PathBuf,
+    lines: Lines<BufReader<File>>,
+    line: usize,
+    ended: bool,
+    state: GameState,
+}
+
+impl TraceMemReader {
+    pub fn open(path: &Path) -> Result<Box<Self>> {
+        let file =
+            File::open(path).map_err(|e| anyhow!("couldn't open {}: {e}", path.display()))?;
+        Ok(Box::new(TraceMemReader {
+            path: path.to_path_buf(),
+            lines: BufReader::new(file).lines(),
+            line: 0,
+            ended: false,
+            state: GameState::default(),
+        }))
+    }
+}
+
+impl SnapshotReader for TraceMemReader {
+    fn read_snapshot(&mut self) -> Result<()> {
+        loop {
+            let Some(line) = self.lines.next() else {
+                self.ended = true;
+                return Err(anyhow!("end of the trace"));
+            };
+            self.line += 1;
+            let line = line.map_err(|e| anyhow!("couldn't read {}: {e}", self.path.display()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let message = serde_json::from_str(&line).map_err(|e| {
+                anyhow!(
+                    "line {} of {} isn't a state: {e}",
+                    self.line,
+                    self.path.display()
+                )
+            })?;
+            return match message {
+                HelperMessage::State(state) => {
+                    self.state = state;
+                    Ok(())
+                }
+                HelperMessage::Error(error) => Err(anyhow!("{error}")),
+            };
+        }
+    }
+
+    fn snapshot(&self) -> &GameState {
+        &self.state
+    }
+
+    fn ended(&self) -> bool {
+        self.ended
+    }
+}
@@ -1,44 +1,41 @@
+use crate::area::{Area, AreaMode};
 use crate::memory_reader::mem_reader::MemReader;
-use crate::split_reader::{Area, AreaMode};
-use anyhow::Result;
-use expand_tilde::expand_tilde;
+use crate::save_reader::{SaveData, save_files};
+use anyhow::{Result, anyhow};
+use log::{debug, error};
 use procfs::process::{MMPermissions, MMapPath, Process};
-use roxmltree::{Document, NodeId};
-use std::fs;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::{
-    fs::File,
+    fs::{self, File},
     io::{Read, Seek, SeekFrom},
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime},
 };
 
 pub(super) struct VanillaMemReader {
     memory: File,
     offset: u64,
     last_file_time: f64,
+    save_location: String,
+    /// Save file of the slot being played.
+    save_path: PathBuf,
+    save: Option<(SystemTime, SaveData)>,
+    /// When the save file was last checked for changes.
+    save_checked: Option<Instant>,
 }
 
 static WARNED: AtomicBool = AtomicBool::new(false);
 
+/// How often the save file is checked for changes: the game only writes it now and then.
+const SAVE_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
 impl VanillaMemReader {
     pub async fn new(save_location: String) -> Result<Option<Box<Self>>> {
         tokio::task::spawn_blocking(move || {
-            let mut times: Vec<[u8; 8]> = Vec::with_capacity(3);
-            for i in 0..3 {
-                let file_path = expand_tilde(&save_location)?.join(format!("{}.celeste", i));
-                if file_path.exists() {
-                    let t = fs::read_to_string(file_path)?;
-                    let u = Document::parse(t.as_str());
-                    if let Ok(doc) = u {
-                        for child in doc.get_node(NodeId::new(1)).unwrap().children() {
-                            if child.tag_name().name() == "Time"
-                                && let Some(time_str) = child.text()
-                                && let Ok(time) = time_str.parse::<u64>()
-                            {
-                                times.push(time.to_le_bytes());
-                                break;
-                            }
-                        }
-                    }
+            let mut times: Vec<(PathBuf, [u8; 8])> = Vec::new();
+            for file_path in save_files(&save_location).unwrap_or_default() {
+                if let Ok(save) = SaveData::read(&file_path) {
+                    times.push((file_path, save.time.to_le_bytes()));
                 }
             }
             if times.is_empty() {
@@ -60,6 +57,7 @@ impl VanillaMemReader {
                 if let Ok(mut memory) = process.mem()
                     && let Ok(maps) = process.maps()
                 {
+                    let mut best: Option<(usize, u64)> = None;
                     for map in maps {
                         if map.perms.contains(
                             MMPermissions::READ | MMPermissions::WRITE | MMPermissions::PRIVATE,
@@ -75,27 +73,41 @@ impl VanillaMemReader {
                             if memory.read_exact(&mut buf).is_err() {
                                 continue;
                             };
-                            for time in &times {
-                                let needle: [u8; 8] = *time;
-                                for i in (0..=buf.len() - 24).step_by(8) {
-                                    if buf[i..i + 8] == needle
+                            // Needles are ordered by save recency, so only stop early on the
+                            // most recent one and otherwise keep the best ranked match.
+                            for (rank, (_, needle)) in times.iter().enumerate() {
+                                if best.as_ref().is_some_and(|&(best_rank, _)| best_rank <= rank) {
+                                    break;
+                                }
+                                for i in (16..=buf.len() - 24).step_by(8) {
+                                    if buf[i..i + 8] == *needle
                                         && buf[i - 16..i].iter().all(|&b| b == 0)
                                     {
-                                        let position = map.address.0 + i as u64;
-                                        return Ok(Some(Box::new(VanillaMemReader {
-                                            memory,
-                                            offset: position - 0x28,
-                                            last_file_time: f64::INFINITY,
-                                        })));
+                                        best = Some((rank, map.address.0 + i as u64));
+                                        break;
                                     }
                                 }
                             }
+                            if best.is_some_and(|(rank, _)| rank == 0) {
+                                break;
+                            }
                         }
                     }
+                    if let Some((rank, position)) = best {
+                        return Ok(Some(Box::new(VanillaMemReader {
+                            memory,
+                            offset: position - 0x28,
+                            last_file_time: f64::INFINITY,
+                            save_location,
+                            save_path: times.swap_remove(rank).0,
+                            save: None,
+                            save_checked: None,
+                        })));
+                    }
                 }
                 else if !WARNED.swap(true, Ordering::Relaxed) {
-                    println!("Couldn't read the memory from the Celeste process because of missing permissions.");
-                    println!("Read more at https://github.com/Paloys/linsplit#linsplit-tells-me-the-games-memory-couldnt-be-read")
+                    error!("Couldn't read the memory from the Celeste process because of missing permissions.");
+                    error!("Read more at https://github.com/Paloys/linsplit#linsplit-tells-me-the-games-memory-couldnt-be-read")
                 }
             }
             Ok(None)
@@ -103,6 +115,46 @@ impl VanillaMemReader {
         .await?
     }
 
+    /// The vanilla AutosplitterInfo has no file flags, so they are read from the save file of
+    /// the slot being played: the one the reader was found with, or the last one the game
+    /// wrote since. It's checked every few seconds, and the last save read is kept if it
+    /// can't be read again, like while the game is writing it.
+    fn save_data(&mut self) -> Result<&SaveData> {
+        if self
+            .save_checked
+            .is_none_or(|checked| checked.elapsed() >= SAVE_CHECK_INTERVAL)
+        {
+            self.save_checked = Some(Instant::now());
+            if let Err(error) = self.reload_save() {
+                debug!("Couldn't read the save file: {error}");
+            }
+        }
+        self.save
+            .as_ref()
+            .map(|(_, save)| save)
+            .ok_or_else(|| anyhow!("couldn't read {}", self.save_path.display()))
+    }
+
+    fn reload_save(&mut self) -> Result<()> {
+        let current = fs::metadata(&self.save_path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        // Playing another slot makes the game write it
+        if let Some(latest) = save_files(&self.save_location)?.into_iter().next()
+            && latest != self.save_path
+            && fs::metadata(&latest)?.modified().ok() > current
+        {
+            debug!("Reading the file flags from {}", latest.display());
+            self.save_path = latest;
+            self.save = None;
+        }
+        let modified = fs::metadata(&self.save_path)?.modified()?;
+        if self.save.as_ref().is_none_or(|(last, _)| *last != modified) {
+            self.save = Some((modified, SaveData::read(&self.save_path)?));
+        }
+        Ok(())
+    }
+
     fn read_bits<const COUNT: usize>(&mut self, offset: u64) -> Result<[u8; COUNT]> {
         self.memory.seek(SeekFrom::Start(self.offset + offset))?;
         let mut buf = [0; COUNT];
@@ -142,6 +194,10 @@ impl MemReader for VanillaMemReader {
     fn level_name(&mut self) -> Result<String> {
         // Celeste.Instance.AutosplitterInfo.Level
         let address = u64::from_le_bytes(self.read_bits(0)?);
+        if address == 0 {
+            // No level is loaded, e.g. in the menus
+            return Ok(String::new());
+        }
         let length = u32::from_le_bytes(self.read_global_bits(address + 0x10)?);
         Ok(String::from_utf16(&self.read_vec_global_bits(
             address + 0x14,
@@ -204,4 +260,22 @@ impl MemReader for VanillaMemReader {
         // Let's see if that works
         Ok(self.last_file_time == 0. && self.last_file_time < self.game_time()?)
     }
+
+    fn is_debug(&mut self) -> Result<bool> {
+        // Vanilla builds don't ship the debug mode
+        Ok(false)
+    }
+
+    fn assist_mode(&mut self) -> Result<bool> {
+        Ok(self.save_data()?.assist_mode)
+    }
+
+    fn variants_mode(&mut self) -> Result<bool> {
+        Ok(self.save_data()?.variant_mode)
+    }
+
+    fn chapter_strawberries(&mut self) -> Result<u32> {
+        // Celeste.Instance.AutosplitterInfo.ChapterStrawberries
+        Ok(u32::from_le_bytes(self.read_bits(0x20)?))
+    }
 }
@@ -0,0 +1,133 @@
+use anyhow::{Result, anyhow};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::history::format_time;
+use crate::memory_reader::game_data::GameState;
+use crate::split_reader::expression::Variable;
+
+/// The files are rewritten at most this often, the timer changing all the time.
+const UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Files written by default, and their format.
+const DEFAULT_FORMATS: &[(&str, &str)] = &[
+    ("chapter", "{chapter} {side}"),
+    ("room", "{room}"),
+    ("berries", "{berries}"),
+    ("hearts", "{hearts}"),
+    ("cassettes", "{cassettes}"),
+    ("segment", "{segment}"),
+    ("time", "{time}"),
+];
+
+/// A piece of a format string.
+#[derive(Debug)]
+enum Part {
+    Text(String),
+    Variable(Variable),
+    /// Name of the chapter, as shown in the game.
+    Chapter,
+    /// Name of the current segment of the splits.
+    Segment,
+    /// Time sent to the timer.
+    Time,
+}
+
+/// Parses a format string, like `Room {room} ({berries} berries)`.
+fn parse_format(format: &str) -> Result<Vec<Part>> {
+    let mut parts = vec![];
+    let mut rest = format;
+    while let Some(start) = rest.find('{') {
+        if start > 0 {
+            parts.push(Part::Text(String::from(&rest[..start])));
+        }
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| anyhow!("unclosed {{ in \"{format}\""))?;
+        let name = &rest[start + 1..start + end];
+        parts.push(match name {
+            "chapter" => Part::Chapter,
+            "segment" => Part::Segment,
+            "time" => Part::Time,
+            _ => Part::Variable(
+                Variable::from_name(name).map_err(|e| anyhow!("in \"{format}\": {e}"))?,
+            ),
+        });
+        rest = &rest[start + end + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push(Part::Text(String::from(rest)));
+    }
+    Ok(parts)
+}
+
+/// Text files for OBS, replaced when their content changes.
+pub struct ObsOutput {
+    dir: PathBuf,
+    files: Vec<(String, Vec<Part>)>,
+    contents: HashMap<String, String>,
+    last_update: Option<Instant>,
+}
+
+impl ObsOutput {
+    /// Writes the default files in `dir`, with their formats replaced or completed by
+    /// `formats`. An empty format disables a file.
+    pub fn new(dir: PathBuf, formats: &HashMap<String, String>) -> Result<Self> {
+        fs::create_dir_all(&dir).map_err(|e| anyhow!("couldn't create {}: {e}", dir.display()))?;
+        let mut all_formats: Vec<(String, String)> = DEFAULT_FORMATS
+            .iter()
+            .filter(|(name, _)| !formats.contains_key(*name))
+            .map(|(name, format)| (String::from(*name), String::from(*format)))
+            .collect();
+        all_formats.extend(
+            formats
+                .iter()
+                .map(|(name, format)| (name.clone(), format.clone())),
+        );
+        let files = all_formats
+            .into_iter()
+            .filter(|(_, format)| !format.is_empty())
+            .map(|(name, format)| Ok((name, parse_format(&format)?)))
+            .collect::<Result<_>>()?;
+        Ok(ObsOutput {
+            dir,
+            files,
+            contents: HashMap::new(),
+            last_update: None,
+        })
+    }
+
+    pub fn update(&mut self, state: &GameState, segment: Option<&str>, time: f64) -> Result<()> {
+        if self
+            .last_update
+            .is_some_and(|last_update| last_update.elapsed() < UPDATE_INTERVAL)
+        {
+            return Ok(());
+        }
+        self.last_update = Some(Instant::now());
+        for (name, parts) in &self.files {
+            let mut text = String::new();
+            for part in parts {
+                match part {
+                    Part::Text(part) => text += part,
+                    Part::Variable(variable) => text += &variable.value(state).to_string(),
+                    Part::Chapter => text += state.area_id.name(),
+                    Part::Segment => text += segment.unwrap_or_default(),
+                    Part::Time => text += &format_time(time),
+                }
+            }
+            if self.contents.get(name) != Some(&text) {
+                // Written next to the file then renamed, so OBS never reads half of it
+                let path = self.dir.join(format!("{name}.txt"));
+                let temporary = self.dir.join(format!(".{name}.txt.tmp"));
+                fs::write(&temporary, &text)?;
+                fs::rename(&temporary, &path)
+                    .map_err(|e| anyhow!("couldn't write {}: {e}", path.display()))?;
+                self.contents.insert(name.clone(), text);
+            }
+        }
+        Ok(())
+    }
+}
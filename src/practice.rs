@@ -0,0 +1,361 @@
+use anyhow::{Result, anyhow};
+use log::{info, warn};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::{ReaderOptions, data_dir};
+use crate::history::format_time;
+use crate::memory_reader::game_data::{GameData, GameState};
+use crate::memory_reader::helper_reader::HelperCommand;
+use crate::split_reader::{Area, AreaMode};
+
+const PRACTICE_FILE: &str = "practice.jsonl";
+
+/// The rooms a practiced segment goes from and to.
+pub struct Segment {
+    pub area: Area,
+    pub side: AreaMode,
+    pub from: String,
+    pub to: String,
+}
+
+/// How an attempt ended.
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Finished,
+    /// The first room was entered again before reaching the last one.
+    Restarted,
+}
+
+/// An attempt of a segment, stored as a line of the practice file.
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug)]
+pub struct PracticeAttempt {
+    /// Seconds since the Unix epoch.
+    pub started: u64,
+    pub area: Area,
+    pub side: AreaMode,
+    pub from: String,
+    pub to: String,
+    pub outcome: Outcome,
+    /// Chapter time from entering the first room to entering the last one, or to the end of
+    /// the attempt if it wasn't finished.
+    pub time: f64,
+    /// Room the attempt ended in.
+    pub room: String,
+}
+
+/// Times a segment from room to room, from the changes of the chapter timer.
+struct Practice {
+    segment: Segment,
+    path: PathBuf,
+    /// Chapter time when the first room was entered, while an attempt is running.
+    start: Option<(u64, f64)>,
+    /// Room of the last state, unknown until the first one.
+    last_room: Option<String>,
+    /// Times of the attempts finished during this session.
+    times: Vec<f64>,
+    attempts: usize,
+    /// Best time of all the attempts recorded, this session included.
+    best_ever: Option<f64>,
+}
+
+impl Practice {
+    fn new(segment: Segment, path: PathBuf) -> Result<Self> {
+        let best_ever = load(&path)?
+            .iter()
+            .filter(|attempt| {
+                attempt.outcome == Outcome::Finished
+                    && attempt.area == segment.area
+                    && attempt.side == segment.side
+                    && attempt.from.eq_ignore_ascii_case(&segment.from)
+                    && attempt.to.eq_ignore_ascii_case(&segment.to)
+            })
+            .map(|attempt| attempt.time)
+            .reduce(f64::min);
+        Ok(Practice {
+            segment,
+            path,
+            start: None,
+            last_room: None,
+            times: vec![],
+            attempts: 0,
+            best_ever,
+        })
+    }
+
+    /// Follows a new state of the game, and returns the attempt that just ended if any.
+    fn update(&mut self, state: &GameState) -> Option<PracticeAttempt> {
+        let segment = &self.segment;
+        if state.area_id != segment.area || state.area_difficulty != segment.side {
+            // Leaving the chapter abandons the attempt
+            self.start = None;
+            self.last_room = Some(String::new());
+            return None;
+        }
+        let room = state.level_name.as_str();
+        let entered =
+            !room.is_empty() && self.last_room.as_deref().is_some_and(|last| last != room);
+        self.last_room = Some(room.to_string());
+        let ended = match self.start {
+            // The chapter was restarted, its timer with it
+            Some((_, start)) if state.level_time < start => {
+                self.start = None;
+                None
+            }
+            Some(_) if entered && room.eq_ignore_ascii_case(&segment.to) => Some(Outcome::Finished),
+            Some(_) if entered && room.eq_ignore_ascii_case(&segment.from) => {
+                Some(Outcome::Restarted)
+            }
+            _ => None,
+        };
+        let attempt = ended.and_then(|outcome| {
+            let (started, start) = self.start.take()?;
+            Some(PracticeAttempt {
+                started,
+                area: segment.area,
+                side: segment.side,
+                from: segment.from.clone(),
+                to: segment.to.clone(),
+                outcome,
+                time: state.level_time - start,
+                room: room.to_string(),
+            })
+        });
+        if entered && room.eq_ignore_ascii_case(&segment.from) {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs());
+            self.start = Some((now, state.level_time));
+        }
+        attempt
+    }
+
+    /// Records an attempt and prints it with the statistics of the session.
+    fn record(&mut self, attempt: &PracticeAttempt) -> Result<()> {
+        self.attempts += 1;
+        let summary = match attempt.outcome {
+            Outcome::Finished => {
+                self.times.push(attempt.time);
+                let best = self
+                    .times
+                    .iter()
+                    .copied()
+                    .reduce(f64::min)
+                    .unwrap_or(attempt.time);
+                let count = self.times.len() as f64;
+                let average = self.times.iter().sum::<f64>() / count;
+                let deviation = (self
+                    .times
+                    .iter()
+                    .map(|time| (time - average).powi(2))
+                    .sum::<f64>()
+                    / count)
+                    .sqrt();
+                let record = match self.best_ever {
+                    Some(previous) if attempt.time < previous => {
+                        format!(", new best, was {}", format_time(previous))
+                    }
+                    Some(previous) => format!(", all-time best {}", format_time(previous)),
+                    None => String::new(),
+                };
+                self.best_ever = Some(
+                    self.best_ever
+                        .map_or(attempt.time, |best| best.min(attempt.time)),
+                );
+                format!(
+                    "{}  best {}  average {}  deviation {:.3}{record}",
+                    format_time(attempt.time),
+                    format_time(best),
+                    format_time(average),
+                    deviation
+                )
+            }
+            Outcome::Restarted => String::from("restarted"),
+        };
+        println!(
+            "#{:<4} {summary}  ({}/{} finished)",
+            self.attempts,
+            self.times.len(),
+            self.attempts
+        );
+        self.save(attempt)
+    }
+
+    /// Appends an attempt to the practice file.
+    fn save(&self, attempt: &PracticeAttempt) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| anyhow!("couldn't open {}: {e}", self.path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(attempt)?)?;
+        Ok(())
+    }
+}
+
+/// Reads every attempt of the practice file at `path`, oldest first.
+fn load(path: &Path) -> Result<Vec<PracticeAttempt>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(_) => return Ok(vec![]),
+    };
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| anyhow!("in {} line {}: {e}", path.display(), number + 1))
+        })
+        .collect()
+}
+
+/// `linsplit practice`: times every attempt of a segment, from room to room, until stopped.
+pub async fn run(segment: Segment, path: Option<PathBuf>, reader: &ReaderOptions) -> Result<()> {
+    if segment.from.eq_ignore_ascii_case(&segment.to) {
+        return Err(anyhow!("the first and the last rooms have to be different"));
+    }
+    let path = match path {
+        Some(path) => path,
+        None => data_dir()?.join(PRACTICE_FILE),
+    };
+    println!(
+        "Practicing {:?} {:?} from {} to {}, attempts are saved to {}.",
+        segment.area,
+        segment.side,
+        segment.from,
+        segment.to,
+        path.display()
+    );
+    let mut practice = Practice::new(segment, path)?;
+    let helper = reader.helper.as_ref().map(|path| HelperCommand {
+        path: path.clone(),
+        save_location: reader.save_location.clone(),
+        polling_interval: reader.polling_interval,
+    });
+    let mut game_data = GameData::new(reader.save_location.clone(), helper).await;
+    info!("Enter {} to start an attempt.", practice.segment.from);
+    loop {
+        match game_data.update() {
+            Ok(()) => {
+                if let Some(attempt) = practice.update(&game_data.state) {
+                    practice.record(&attempt)?;
+                }
+            }
+            Err(error) => {
+                if error.consecutive_failures == 1 {
+                    warn!("Couldn't read the game's state: {error}");
+                }
+                if game_data.should_reattach() {
+                    warn!("Lost Celeste, searching for it again.");
+                    game_data.reattach().await;
+                }
+            }
+        }
+        tokio::time::sleep(reader.polling_interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn practice() -> Practice {
+        let segment = Segment {
+            area: Area::OldSite,
+            side: AreaMode::ASide,
+            from: String::from("a-01"),
+            to: String::from("a-03"),
+        };
+        Practice::new(segment, PathBuf::from("/nonexistent/practice.jsonl")).unwrap()
+    }
+
+    fn state(room: &str, level_time: f64) -> GameState {
+        GameState {
+            area_id: Area::OldSite,
+            area_difficulty: AreaMode::ASide,
+            level_name: String::from(room),
+            level_time,
+            ..GameState::default()
+        }
+    }
+
+    #[test]
+    fn times_from_the_first_room_to_the_last() {
+        let mut practice = practice();
+        assert!(practice.update(&state("a-00", 1.)).is_none());
+        assert!(practice.update(&state("a-01", 2.)).is_none());
+        assert!(practice.update(&state("a-02", 5.)).is_none());
+        let attempt = practice.update(&state("A-03", 9.5)).unwrap();
+        assert_eq!(attempt.outcome, Outcome::Finished);
+        assert_eq!(attempt.time, 7.5);
+        assert_eq!(attempt.room, "A-03");
+        // Only one attempt per entry of the first room
+        assert!(practice.update(&state("a-04", 12.)).is_none());
+        assert!(practice.update(&state("a-03", 13.)).is_none());
+    }
+
+    #[test]
+    fn entering_the_first_room_again_restarts() {
+        let mut practice = practice();
+        practice.update(&state("a-00", 1.));
+        practice.update(&state("a-01", 2.));
+        practice.update(&state("a-02", 4.));
+        let attempt = practice.update(&state("a-01", 6.)).unwrap();
+        assert_eq!(attempt.outcome, Outcome::Restarted);
+        assert_eq!(attempt.time, 4.);
+        let attempt = practice.update(&state("a-03", 10.)).unwrap();
+        assert_eq!(attempt.outcome, Outcome::Finished);
+        assert_eq!(attempt.time, 4.);
+    }
+
+    #[test]
+    fn being_in_the_first_room_at_the_start_does_not_start() {
+        let mut practice = practice();
+        assert!(practice.update(&state("a-01", 2.)).is_none());
+        assert!(practice.update(&state("a-03", 5.)).is_none());
+    }
+
+    #[test]
+    fn leaving_the_chapter_drops_the_attempt() {
+        let mut practice = practice();
+        practice.update(&state("a-00", 1.));
+        practice.update(&state("a-01", 2.));
+        let menu = GameState {
+            area_id: Area::Menu,
+            ..GameState::default()
+        };
+        assert!(practice.update(&menu).is_none());
+        assert!(practice.update(&state("a-03", 0.5)).is_none());
+        // Coming back in the first room starts a new attempt
+        practice.update(&state("a-01", 0.5));
+        assert_eq!(practice.update(&state("a-03", 3.)).unwrap().time, 2.5);
+    }
+
+    #[test]
+    fn restarting_the_chapter_drops_the_attempt() {
+        let mut practice = practice();
+        practice.update(&state("a-00", 1.));
+        practice.update(&state("a-01", 2.));
+        practice.update(&state("a-02", 4.));
+        assert!(practice.update(&state("a-02", 0.)).is_none());
+        assert!(practice.update(&state("a-03", 1.)).is_none());
+    }
+
+    #[test]
+    fn other_sides_are_ignored() {
+        let mut practice = practice();
+        let b_side = |room: &str, level_time| GameState {
+            area_difficulty: AreaMode::BSide,
+            ..state(room, level_time)
+        };
+        practice.update(&b_side("a-00", 1.));
+        practice.update(&b_side("a-01", 2.));
+        assert!(practice.update(&b_side("a-03", 5.)).is_none());
+    }
+}
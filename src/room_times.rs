@@ -0,0 +1,150 @@
+use anyhow::{Result, anyhow};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::data_dir;
+use crate::memory_reader::game_data::GameState;
+use crate::split_reader::{Area, AreaMode};
+
+const BESTS_FILE: &str = "room_bests.json";
+
+/// Time spent in a room during the current attempt.
+#[derive(serde_derive::Serialize, Debug)]
+pub struct RoomTime {
+    pub area: String,
+    pub side: String,
+    pub room: String,
+    pub time: f64,
+    /// Best time of the room before this attempt.
+    pub best: f64,
+}
+
+/// Records the time spent in each room, from the differences of the chapter timer when
+/// changing rooms, and keeps the best time of every room in the data directory.
+pub struct RoomTimes {
+    bests_path: PathBuf,
+    /// Best times, keyed by [`room_key`].
+    bests: HashMap<String, f64>,
+    export_path: Option<PathBuf>,
+    attempt: Vec<RoomTime>,
+    /// Room the player is in, and the chapter time when they entered it.
+    current: Option<(Area, AreaMode, String, f64)>,
+}
+
+fn room_key(area: Area, mode: AreaMode, room: &str) -> String {
+    format!("{area:?}/{mode:?}/{room}")
+}
+
+impl RoomTimes {
+    pub fn new(export_path: Option<PathBuf>) -> Result<Self> {
+        let bests_path = data_dir()?.join(BESTS_FILE);
+        let bests = match fs::read_to_string(&bests_path) {
+            Ok(text) => serde_json::from_str(&text)
+                .map_err(|e| anyhow!("in {}: {e}", bests_path.display()))?,
+            Err(_) => HashMap::new(),
+        };
+        Ok(RoomTimes {
+            bests_path,
+            bests,
+            export_path,
+            attempt: vec![],
+            current: None,
+        })
+    }
+
+    /// Updates the timings with a new state of the game. Returns the time of the room that
+    /// was just left, if any.
+    pub fn update(&mut self, state: &GameState) -> Result<Option<&RoomTime>> {
+        if state.level_name.is_empty() || state.area_id == Area::Menu {
+            return Ok(None);
+        }
+        let entered = (
+            state.area_id,
+            state.area_difficulty,
+            state.level_name.clone(),
+            state.level_time,
+        );
+        let Some((area, mode, room, start)) = self.current.take() else {
+            self.current = Some(entered);
+            return Ok(None);
+        };
+        if area != state.area_id || mode != state.area_difficulty || state.level_time < start {
+            // The chapter was restarted or left, this is a new attempt
+            self.attempt.clear();
+            self.current = Some(entered);
+            return Ok(None);
+        }
+        if room == state.level_name {
+            self.current = Some((area, mode, room, start));
+            return Ok(None);
+        }
+        self.current = Some(entered);
+        let time = state.level_time - start;
+        let best = self
+            .bests
+            .entry(room_key(area, mode, &room))
+            .or_insert(time);
+        let previous_best = *best;
+        *best = best.min(time);
+        self.attempt.push(RoomTime {
+            area: format!("{area:?}"),
+            side: format!("{mode:?}"),
+            room,
+            time,
+            best: previous_best,
+        });
+        self.save_bests()?;
+        self.export()?;
+        Ok(self.attempt.last())
+    }
+
+    fn save_bests(&self) -> Result<()> {
+        if let Some(dir) = self.bests_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.bests_path, serde_json::to_string_pretty(&self.bests)?)
+            .map_err(|e| anyhow!("couldn't write {}: {e}", self.bests_path.display()))
+    }
+
+    /// Writes the rooms of the current attempt to the export file, as JSON if its extension
+    /// is `.json` and as CSV otherwise.
+    fn export(&self) -> Result<()> {
+        let Some(path) = &self.export_path else {
+            return Ok(());
+        };
+        let text = if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            serde_json::to_string_pretty(&self.attempt)?
+        } else {
+            to_csv(&self.attempt)
+        };
+        fs::write(path, text).map_err(|e| anyhow!("couldn't write {}: {e}", path.display()))
+    }
+}
+
+fn to_csv(rooms: &[RoomTime]) -> String {
+    let mut csv = String::from("area,side,room,time,best,delta\n");
+    for room in rooms {
+        csv += &format!(
+            "{},{},{},{:.3},{:.3},{:+.3}\n",
+            room.area,
+            room.side,
+            csv_field(&room.room),
+            room.time,
+            room.best,
+            room.time - room.best
+        );
+    }
+    csv
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
@@ -0,0 +1,273 @@
+use anyhow::{Result, anyhow};
+use expand_tilde::expand_tilde;
+use log::debug;
+use roxmltree::{Document, Node};
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use crate::area::{Area, AreaMode};
+
+/// Lists every save slot in `save_location`, most recently modified first.
+/// `settings.celeste` and Everest's mod settings/save files are skipped.
+pub fn save_files(save_location: &str) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<(SystemTime, PathBuf)> = fs::read_dir(expand_tilde(save_location)?)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| is_save_slot(path))
+        .map(|path| {
+            let modified = fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            (modified, path)
+        })
+        .collect();
+    files.sort_by_key(|(modified, _)| Reverse(*modified));
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+
+fn is_save_slot(path: &Path) -> bool {
+    if path
+        .extension()
+        .is_none_or(|extension| extension != "celeste")
+        || !path.is_file()
+    {
+        return false;
+    }
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .is_some_and(|stem| {
+            stem != "settings" && !stem.starts_with("modsettings") && !stem.contains("modsave")
+        })
+}
+
+#[derive(Debug, Clone)]
+pub struct AreaModeStats {
+    pub mode: AreaMode,
+    pub completed: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct AreaStats {
+    pub area: Area,
+    pub modes: Vec<AreaModeStats>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SaveData {
+    /// File time, in ticks of 100ns.
+    pub time: u64,
+    pub total_deaths: u32,
+    pub total_strawberries: u32,
+    pub cheat_mode: bool,
+    pub assist_mode: bool,
+    pub variant_mode: bool,
+    pub areas: Vec<AreaStats>,
+}
+
+impl SaveData {
+    pub fn read(file_path: &Path) -> Result<Self> {
+        Self::parse(&fs::read_to_string(file_path)?)
+    }
+
+    fn parse(text: &str) -> Result<Self> {
+        let doc = Document::parse(text)?;
+        let root = doc.root_element();
+        if root.tag_name().name() != "SaveData" {
+            return Err(anyhow!("not a Celeste save file"));
+        }
+        let mut save = SaveData::default();
+        for child in root.children() {
+            match child.tag_name().name() {
+                // The file time is what the vanilla reader looks for in the game's memory
+                "Time" => {
+                    save.time = child
+                        .text()
+                        .and_then(|text| text.trim().parse().ok())
+                        .ok_or(anyhow!("invalid value for <Time>"))?
+                }
+                "TotalDeaths" => save.total_deaths = parse_text(child),
+                "TotalStrawberries" => save.total_strawberries = parse_text(child),
+                "CheatMode" => save.cheat_mode = parse_text(child),
+                "AssistMode" => save.assist_mode = parse_text(child),
+                "VariantMode" => save.variant_mode = parse_text(child),
+                "Areas" => save.areas.extend(
+                    child
+                        .children()
+                        .filter(|n| n.has_tag_name("AreaStats"))
+                        .filter_map(AreaStats::parse),
+                ),
+                _ => {}
+            }
+        }
+        Ok(save)
+    }
+}
+
+impl AreaStats {
+    /// Parses the stats of an area, `None` for the areas that aren't Celeste's, like the ones
+    /// of the mods.
+    fn parse(node: Node) -> Option<Self> {
+        let area = node
+            .attribute("ID")
+            .and_then(|id| id.trim().parse::<i32>().ok())
+            .and_then(|id| Area::try_from(id).ok())
+            .filter(|&area| area != Area::Unknown);
+        let Some(area) = area else {
+            debug!("Skipping the stats of the area {:?}", node.attribute("SID"));
+            return None;
+        };
+        let modes = node
+            .children()
+            .find(|n| n.has_tag_name("Modes"))
+            .map(|modes| {
+                modes
+                    .children()
+                    .filter(|n| n.has_tag_name("AreaModeStats"))
+                    .enumerate()
+                    .filter_map(|(i, stats)| {
+                        Some(AreaModeStats::parse(
+                            stats,
+                            AreaMode::try_from(i as i32).ok()?,
+                        ))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Some(AreaStats { area, modes })
+    }
+}
+
+impl AreaModeStats {
+    fn parse(node: Node, mode: AreaMode) -> Self {
+        AreaModeStats {
+            mode,
+            completed: parse_attribute(node, "Completed"),
+        }
+    }
+}
+
+/// Parses the text of a node, treating an empty or invalid node as the default value: one
+/// value LinSplit doesn't understand shouldn't make the whole save unreadable.
+fn parse_text<T: FromStr + Default>(node: Node) -> T {
+    let text = node.text().unwrap_or_default().trim();
+    if text.is_empty() {
+        return T::default();
+    }
+    text.parse().unwrap_or_else(|_| {
+        debug!(
+            "Ignoring the invalid value {text:?} of <{}>",
+            node.tag_name().name()
+        );
+        T::default()
+    })
+}
+
+/// Parses an attribute of a node, treating a missing or invalid attribute as the default value.
+fn parse_attribute<T: FromStr + Default>(node: Node, name: &str) -> T {
+    let value = node.attribute(name).unwrap_or_default().trim();
+    if value.is_empty() {
+        return T::default();
+    }
+    value.parse().unwrap_or_else(|_| {
+        debug!(
+            "Ignoring the invalid value {value:?} of {name} in <{}>",
+            node.tag_name().name()
+        );
+        T::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAVE: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<SaveData xmlns:xsd="http://www.w3.org/2001/XMLSchema">
+  <Time>1234567</Time>
+  <TotalDeaths>42</TotalDeaths>
+  <TotalStrawberries>not a number</TotalStrawberries>
+  <AssistMode>true</AssistMode>
+  <Areas>
+    <AreaStats ID="1" SID="Celeste/1-ForsakenCity">
+      <Modes>
+        <AreaModeStats Completed="true" />
+        <AreaModeStats Completed="false" />
+        <AreaModeStats />
+      </Modes>
+    </AreaStats>
+    <AreaStats ID="11" SID="SpringCollab2020/0-Lobbies/1-Beginner">
+      <Modes>
+        <AreaModeStats Completed="true" />
+      </Modes>
+    </AreaStats>
+  </Areas>
+</SaveData>"#;
+
+    #[test]
+    fn reads_a_save() {
+        let save = SaveData::parse(SAVE).unwrap();
+        assert_eq!(save.time, 1234567);
+        assert_eq!(save.total_deaths, 42);
+        // An invalid value doesn't make the whole save unreadable
+        assert_eq!(save.total_strawberries, 0);
+        assert!(save.assist_mode);
+        assert!(!save.variant_mode);
+        let modes: Vec<_> = save.areas[0]
+            .modes
+            .iter()
+            .map(|stats| (stats.mode, stats.completed))
+            .collect();
+        assert_eq!(
+            modes,
+            [
+                (AreaMode::ASide, true),
+                (AreaMode::BSide, false),
+                (AreaMode::CSide, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_the_areas_of_mods() {
+        let save = SaveData::parse(SAVE).unwrap();
+        let areas: Vec<_> = save.areas.iter().map(|stats| stats.area).collect();
+        assert_eq!(areas, [Area::ForsakenCity]);
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(SaveData::parse("<Settings><Fullscreen>true</Fullscreen></Settings>").is_err());
+        assert!(SaveData::parse("<SaveData><Time>soon</Time></SaveData>").is_err());
+    }
+
+    #[test]
+    fn lists_the_most_recent_slots_first() {
+        let dir = std::env::temp_dir().join(format!("linsplit-saves-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let files = [
+            ("0.celeste", 20),
+            ("1.celeste", 30),
+            ("2.celeste", 10),
+            ("settings.celeste", 40),
+            ("modsettings-Everest.celeste", 40),
+            ("1-modsave-SpringCollab2020.celeste", 40),
+            ("0.celeste.backup", 40),
+        ];
+        for (name, modified) in files {
+            let file = fs::File::create(dir.join(name)).unwrap();
+            file.set_modified(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(modified))
+                .unwrap();
+        }
+        let slots = save_files(&dir.to_string_lossy());
+        fs::remove_dir_all(&dir).unwrap();
+        let names: Vec<_> = slots
+            .unwrap()
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["1.celeste", "0.celeste", "2.celeste"]);
+    }
+}
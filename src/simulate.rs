@@ -0,0 +1,82 @@
+use anyhow::Result;
+use std::path::Path;
+
+use crate::config::Options;
+use crate::history::format_time;
+use crate::linsplit_data::LinSplitData;
+use crate::split_trace::Conditions;
+use crate::timer::TimerAction;
+
+/// `linsplit simulate --trace <PATH>`: runs the splits against a recorded trace and prints
+/// when each split fired, and why.
+pub async fn run(options: &Options, trace: &Path) -> Result<bool> {
+    let (data, timer) = LinSplitData::simulation(options, trace)?;
+    data.main_loop().await?;
+    let actions = timer.actions().await;
+    let segments = &timer.segments;
+    let segment = |index: usize| segments.get(index).map_or("?", String::as_str);
+
+    println!("Splits: {}", options.splits);
+    println!("Trace: {}", trace.display());
+    println!();
+    println!("  {:<24} {:>12}  Fired by", "Segment", "Game time");
+    let mut fired = vec![false; segments.len()];
+    for action in &actions {
+        match action {
+            TimerAction::Start { time, conditions } => {
+                print_row("Start", *time, conditions.as_ref())
+            }
+            TimerAction::Split {
+                index,
+                time,
+                conditions,
+            } => {
+                if let Some(fired) = fired.get_mut(*index) {
+                    *fired = true;
+                }
+                print_row(segment(*index), *time, conditions.as_ref())
+            }
+            TimerAction::Reset { index, time } => {
+                let at = if *index < segments.len() {
+                    format!("reset during \"{}\"", segment(*index))
+                } else {
+                    String::from("reset after the end of the run")
+                };
+                println!("  {:<24} {:>12}  {at}", "Reset", format_time(*time));
+            }
+        }
+    }
+    if actions.is_empty() {
+        println!("  The run never started.");
+    }
+
+    let never_fired: Vec<_> = segments
+        .iter()
+        .zip(&fired)
+        .filter(|(_, fired)| !**fired)
+        .map(|(segment, _)| segment.as_str())
+        .collect();
+    println!();
+    if never_fired.is_empty() {
+        println!("Every split fired.");
+    } else {
+        println!("Never fired:");
+        for segment in &never_fired {
+            println!("  {segment}");
+        }
+    }
+    Ok(never_fired.is_empty())
+}
+
+fn print_row(segment: &str, time: f64, conditions: Option<&Conditions>) {
+    let entries = conditions.map_or(&[][..], Conditions::entries);
+    match entries.split_first() {
+        Some(((name, value), rest)) => {
+            println!("  {segment:<24} {:>12}  {name}: {value}", format_time(time));
+            for (name, value) in rest {
+                println!("  {:<24} {:>12}  {name}: {value}", "", "");
+            }
+        }
+        None => println!("  {segment:<24} {:>12}  manual", format_time(time)),
+    }
+}
@@ -0,0 +1,150 @@
+use crate::split_reader::{Area, AreaMode};
+
+/// A checkpoint of a chapter, reached when entering its room.
+#[derive(Debug)]
+pub struct Checkpoint {
+    pub area: Area,
+    pub mode: AreaMode,
+    /// 1-based index of the checkpoint in its chapter.
+    pub index: u8,
+    pub room: &'static str,
+    pub name: &'static str,
+}
+
+const fn checkpoint(
+    area: Area,
+    mode: AreaMode,
+    index: u8,
+    room: &'static str,
+    name: &'static str,
+) -> Checkpoint {
+    Checkpoint {
+        area,
+        mode,
+        index,
+        room,
+        name,
+    }
+}
+
+use Area::*;
+use AreaMode::*;
+
+/// Every checkpoint of the game, with the room that starts it. These are the rooms the
+/// `ChapterNCheckpointM` splits have always used; C-sides don't have checkpoints.
+#[rustfmt::skip]
+pub const CHECKPOINTS: &[Checkpoint] = &[
+    checkpoint(ForsakenCity, ASide, 1, "6", "Crossing"),
+    checkpoint(ForsakenCity, ASide, 2, "9b", "Chasm"),
+    checkpoint(ForsakenCity, BSide, 1, "04", "Contraption"),
+    checkpoint(ForsakenCity, BSide, 2, "08", "Scrap Pit"),
+    checkpoint(OldSite, ASide, 1, "3", "Intervention"),
+    checkpoint(OldSite, ASide, 2, "end_3", "Awake"),
+    checkpoint(OldSite, BSide, 1, "03", "Combination Lock"),
+    checkpoint(OldSite, BSide, 2, "08b", "Dream Altar"),
+    checkpoint(CelestialResort, ASide, 1, "08-a", "Huge Mess"),
+    checkpoint(CelestialResort, ASide, 2, "09-d", "Elevator Shaft"),
+    checkpoint(CelestialResort, ASide, 3, "00-d", "Presidential Suite"),
+    checkpoint(CelestialResort, BSide, 1, "06", "Staff Quarters"),
+    checkpoint(CelestialResort, BSide, 2, "11", "Library"),
+    checkpoint(CelestialResort, BSide, 3, "16", "Rooftop"),
+    checkpoint(GoldenRidge, ASide, 1, "b-00", "Shrine"),
+    checkpoint(GoldenRidge, ASide, 2, "c-00", "Old Trail"),
+    checkpoint(GoldenRidge, ASide, 3, "d-00", "Cliff Face"),
+    checkpoint(GoldenRidge, BSide, 1, "b-00", "Stepping Stones"),
+    checkpoint(GoldenRidge, BSide, 2, "c-00", "Gusty Fortress"),
+    checkpoint(GoldenRidge, BSide, 3, "d-00", "Summit Approach"),
+    checkpoint(MirrorTemple, ASide, 1, "b-00", "Depths"),
+    checkpoint(MirrorTemple, ASide, 2, "c-00", "Unravelling"),
+    checkpoint(MirrorTemple, ASide, 3, "d-00", "Search"),
+    checkpoint(MirrorTemple, ASide, 4, "e-00", "Rescue"),
+    checkpoint(MirrorTemple, BSide, 1, "b-00", "Central Chamber"),
+    checkpoint(MirrorTemple, BSide, 2, "c-00", "Through the Mirror"),
+    checkpoint(MirrorTemple, BSide, 3, "d-00", "Mix Master"),
+    checkpoint(Reflection, ASide, 1, "00", "Lake"),
+    checkpoint(Reflection, ASide, 2, "04", "Hollows"),
+    checkpoint(Reflection, ASide, 3, "b-00", "Reflection"),
+    checkpoint(Reflection, ASide, 4, "boss-00", "Rock Bottom"),
+    checkpoint(Reflection, ASide, 5, "after-00", "Resolution"),
+    checkpoint(Reflection, BSide, 1, "b-00", "Reflection"),
+    checkpoint(Reflection, BSide, 2, "c-00", "Rock Bottom"),
+    checkpoint(Reflection, BSide, 3, "d-00", "Reprieve"),
+    checkpoint(TheSummit, ASide, 1, "b-00", "500 M"),
+    checkpoint(TheSummit, ASide, 2, "c-00", "1000 M"),
+    checkpoint(TheSummit, ASide, 3, "d-00", "1500 M"),
+    checkpoint(TheSummit, ASide, 4, "e-00b", "2000 M"),
+    checkpoint(TheSummit, ASide, 5, "f-00", "2500 M"),
+    checkpoint(TheSummit, ASide, 6, "g-00", "3000 M"),
+    checkpoint(TheSummit, BSide, 1, "b-00", "500 M"),
+    checkpoint(TheSummit, BSide, 2, "c-01", "1000 M"),
+    checkpoint(TheSummit, BSide, 3, "d-00", "1500 M"),
+    checkpoint(TheSummit, BSide, 4, "e-00", "2000 M"),
+    checkpoint(TheSummit, BSide, 5, "f-00", "2500 M"),
+    checkpoint(TheSummit, BSide, 6, "g-00", "3000 M"),
+    checkpoint(Core, ASide, 1, "a-00", "Into the Core"),
+    checkpoint(Core, ASide, 2, "c-00", "Hot and Cold"),
+    checkpoint(Core, ASide, 3, "d-00", "Heart of the Mountain"),
+    checkpoint(Core, BSide, 1, "a-00", "Into the Core"),
+    checkpoint(Core, BSide, 2, "b-00", "Burning or Freezing"),
+    checkpoint(Core, BSide, 3, "c-01", "Heartbeat"),
+    checkpoint(Farewell, ASide, 1, "a-00", "Singular"),
+    checkpoint(Farewell, ASide, 2, "c-00", "Power Source"),
+    checkpoint(Farewell, ASide, 3, "e-00z", "Remembered"),
+    checkpoint(Farewell, ASide, 4, "f-door", "Event Horizon"),
+    checkpoint(Farewell, ASide, 5, "h-00b", "Determination"),
+    checkpoint(Farewell, ASide, 6, "i-00", "Stubbornness"),
+    checkpoint(Farewell, ASide, 7, "j-00", "Reconciliation"),
+    checkpoint(Farewell, ASide, 8, "j-16", "Farewell"),
+];
+
+pub fn find(area: Area, mode: AreaMode, index: u8) -> Option<&'static Checkpoint> {
+    CHECKPOINTS.iter().find(|checkpoint| {
+        checkpoint.area == area && checkpoint.mode == mode && checkpoint.index == index
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::split_reader::Split;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn finds_checkpoints() {
+        assert_eq!(find(OldSite, BSide, 2).unwrap().room, "08b");
+        assert!(find(OldSite, BSide, 4).is_none());
+        assert!(find(Prologue, ASide, 1).is_none());
+    }
+
+    #[test]
+    fn chapter_checkpoint_splits_have_an_a_side_checkpoint() {
+        for split in Split::iter() {
+            if let Some((area, side, index)) = split.checkpoint()
+                && side.is_none()
+            {
+                assert!(find(area, ASide, index).is_some(), "{split:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn checkpoints_are_numbered_from_one_in_each_side() {
+        assert!(
+            CHECKPOINTS
+                .iter()
+                .all(|checkpoint| checkpoint.mode != CSide)
+        );
+        assert_eq!(CHECKPOINTS[0].index, 1);
+        for pair in CHECKPOINTS.windows(2) {
+            let [last, checkpoint] = pair else {
+                unreachable!()
+            };
+            let expected = if (last.area, last.mode) == (checkpoint.area, checkpoint.mode) {
+                last.index + 1
+            } else {
+                1
+            };
+            assert_eq!(checkpoint.index, expected, "{checkpoint:?}");
+        }
+    }
+}
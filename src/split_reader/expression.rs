@@ -0,0 +1,751 @@
+use anyhow::{Result, anyhow};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::memory_reader::game_data::GameState;
+use crate::split_reader::{Area, AreaMode};
+
+/// A custom split condition written in the splits file after `Expression,`, e.g.
+/// `area == Core && side == B && room == c-01`, `hearts >= 4`, `berries +1` or `enter(b-00)`.
+///
+/// Conditions can be combined with `&&`/`and`, `||`/`or`, `!`/`not` and parentheses.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    /// `variable op value`
+    Compare {
+        variable: Variable,
+        operator: Operator,
+        value: Value,
+    },
+    /// `variable +N`: the variable changed by exactly N since the previous state.
+    Delta {
+        variable: Variable,
+        amount: i64,
+    },
+    /// A boolean variable on its own.
+    Flag(Variable),
+    /// `enter("room")`: the room just changed to this one.
+    Enter(String),
+    /// `exit("room")`: the room just changed from this one.
+    Exit(String),
+}
+
+/// A value of the game state that expressions can refer to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Variable {
+    Area,
+    Side,
+    Room,
+    Berries,
+    ChapterBerries,
+    Hearts,
+    Cassettes,
+    GameTime,
+    LevelTime,
+    ChapterStarted,
+    ChapterComplete,
+    ChapterCassette,
+    ChapterHeart,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Text(String),
+    Area(Area),
+    Mode(AreaMode),
+    Bool(bool),
+    /// The reader in use can't provide this variable.
+    Missing,
+}
+
+/// Short names of the sides, only accepted in conditions, like `side == B`.
+pub const SIDE_LETTERS: [(&str, AreaMode); 3] = [
+    ("A", AreaMode::ASide),
+    ("B", AreaMode::BSide),
+    ("C", AreaMode::CSide),
+];
+
+impl Variable {
+    /// Every variable, by name.
+    pub const ALL: &[(&str, Variable)] = &[
+        ("area", Variable::Area),
+        ("side", Variable::Side),
+        ("room", Variable::Room),
+        ("berries", Variable::Berries),
+        ("chapter_berries", Variable::ChapterBerries),
+        ("hearts", Variable::Hearts),
+        ("cassettes", Variable::Cassettes),
+        ("game_time", Variable::GameTime),
+        ("level_time", Variable::LevelTime),
+        ("chapter_started", Variable::ChapterStarted),
+        ("chapter_complete", Variable::ChapterComplete),
+        ("chapter_cassette", Variable::ChapterCassette),
+        ("chapter_heart", Variable::ChapterHeart),
+    ];
+
+    pub fn from_name(name: &str) -> Result<Self> {
+        Self::ALL
+            .iter()
+            .find(|(variable_name, _)| variable_name.eq_ignore_ascii_case(name))
+            .map(|&(_, variable)| variable)
+            .ok_or_else(|| anyhow!("unknown variable \"{name}\""))
+    }
+
+    pub fn name(self) -> &'static str {
+        Self::ALL
+            .iter()
+            .find(|&&(_, variable)| variable == self)
+            .map(|&(name, _)| name)
+            .unwrap()
+    }
+
+    pub fn value(self, state: &GameState) -> Value {
+        let number = |value: Option<u32>| value.map_or(Value::Missing, |v| Value::Number(v as f64));
+        match self {
+            Variable::Area => Value::Area(state.area_id),
+            Variable::Side => Value::Mode(state.area_difficulty),
+            Variable::Room => Value::Text(state.level_name.clone()),
+            Variable::Berries => Value::Number(state.strawberries as f64),
+            Variable::ChapterBerries => number(state.chapter_strawberries),
+            Variable::Hearts => Value::Number(state.heart_gems as f64),
+            Variable::Cassettes => Value::Number(state.cassettes as f64),
+            Variable::GameTime => Value::Number(state.game_time),
+            Variable::LevelTime => Value::Number(state.level_time),
+            Variable::ChapterStarted => Value::Bool(state.chapter_started),
+            Variable::ChapterComplete => Value::Bool(state.chapter_complete),
+            Variable::ChapterCassette => Value::Bool(state.chapter_cassette_collected),
+            Variable::ChapterHeart => Value::Bool(state.chapter_heart_collected),
+        }
+    }
+
+    fn is_flag(self) -> bool {
+        matches!(
+            self,
+            Variable::ChapterStarted
+                | Variable::ChapterComplete
+                | Variable::ChapterCassette
+                | Variable::ChapterHeart
+        )
+    }
+
+    /// Parses the right hand side of a comparison into a value of the right type.
+    fn parse_value(self, token: &Token) -> Result<Value> {
+        let text = match token {
+            Token::Identifier(text) | Token::Number(text) | Token::Text(text) => text.as_str(),
+            _ => return Err(anyhow!("expected a value after {}", self.name())),
+        };
+        let invalid = || anyhow!("invalid value \"{text}\" for {}", self.name());
+        match self {
+            Variable::Area => Area::from_str(text).map(Value::Area).map_err(|_| invalid()),
+            Variable::Side => SIDE_LETTERS
+                .iter()
+                .find(|(letter, _)| letter.eq_ignore_ascii_case(text))
+                .map(|&(_, side)| Ok(side))
+                .unwrap_or_else(|| AreaMode::from_str(text))
+                .map(Value::Mode)
+                .map_err(|_| invalid()),
+            Variable::Room => Ok(Value::Text(text.to_string())),
+            _ if self.is_flag() => bool::from_str(&text.to_ascii_lowercase())
+                .map(Value::Bool)
+                .map_err(|_| invalid()),
+            _ => f64::from_str(text)
+                .map(Value::Number)
+                .map_err(|_| invalid()),
+        }
+    }
+}
+
+impl Operator {
+    fn symbol(self) -> &'static str {
+        match self {
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+            Operator::Less => "<",
+            Operator::LessOrEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterOrEqual => ">=",
+        }
+    }
+}
+
+impl Value {
+    fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+            (Value::Text(a), Value::Text(b)) => Some(a.to_lowercase().cmp(&b.to_lowercase())),
+            (Value::Area(a), Value::Area(b)) => (*a as i32).partial_cmp(&(*b as i32)),
+            (Value::Mode(a), Value::Mode(b)) => (*a as i32).partial_cmp(&(*b as i32)),
+            (Value::Bool(a), Value::Bool(b)) => a.partial_cmp(b),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Number(number) if number.fract() == 0. => write!(f, "{number}"),
+            Value::Number(number) => write!(f, "{number:.3}"),
+            Value::Text(text) => write!(f, "{text}"),
+            Value::Area(area) => write!(f, "{area:?}"),
+            Value::Mode(mode) => write!(f, "{mode:?}"),
+            Value::Bool(value) => write!(f, "{value}"),
+            Value::Missing => Ok(()),
+        }
+    }
+}
+
+impl Expression {
+    /// Evaluates the expression against the current state of the game and the previous one.
+    /// The conditions it's made of are added to `conditions` if given, with the values they
+    /// were evaluated on and their result.
+    pub fn evaluate(
+        &self,
+        state: &GameState,
+        last_state: &GameState,
+        mut conditions: Option<&mut Vec<(String, String)>>,
+    ) -> bool {
+        let result = match self {
+            // Both sides are evaluated, so that every condition is explained
+            Expression::And(a, b) => {
+                let a = a.evaluate(state, last_state, conditions.as_deref_mut());
+                let b = b.evaluate(state, last_state, conditions.as_deref_mut());
+                return a && b;
+            }
+            Expression::Or(a, b) => {
+                let a = a.evaluate(state, last_state, conditions.as_deref_mut());
+                let b = b.evaluate(state, last_state, conditions.as_deref_mut());
+                return a || b;
+            }
+            Expression::Not(a) => return !a.evaluate(state, last_state, conditions),
+            Expression::Compare {
+                variable,
+                operator,
+                value,
+            } => {
+                let ordering = variable.value(state).compare(value);
+                match operator {
+                    Operator::Equal => ordering == Some(Ordering::Equal),
+                    Operator::NotEqual => ordering.is_some_and(|o| o != Ordering::Equal),
+                    Operator::Less => ordering == Some(Ordering::Less),
+                    Operator::LessOrEqual => ordering.is_some_and(|o| o != Ordering::Greater),
+                    Operator::Greater => ordering == Some(Ordering::Greater),
+                    Operator::GreaterOrEqual => ordering.is_some_and(|o| o != Ordering::Less),
+                }
+            }
+            Expression::Delta { variable, amount } => {
+                match (variable.value(state), variable.value(last_state)) {
+                    (Value::Number(current), Value::Number(last)) => {
+                        current - last == *amount as f64
+                    }
+                    _ => false,
+                }
+            }
+            Expression::Flag(variable) => variable.value(state) == Value::Bool(true),
+            Expression::Enter(room) => {
+                !state
+                    .level_name
+                    .eq_ignore_ascii_case(&last_state.level_name)
+                    && state.level_name.eq_ignore_ascii_case(room)
+            }
+            Expression::Exit(room) => {
+                !state
+                    .level_name
+                    .eq_ignore_ascii_case(&last_state.level_name)
+                    && last_state.level_name.eq_ignore_ascii_case(room)
+            }
+        };
+        if let Some(conditions) = conditions {
+            conditions.push(self.condition(state, last_state, result));
+        }
+        result
+    }
+
+    /// Explains a condition that isn't made of others, with the values it was evaluated on
+    /// and its result.
+    fn condition(
+        &self,
+        state: &GameState,
+        last_state: &GameState,
+        result: bool,
+    ) -> (String, String) {
+        match self {
+            Expression::Compare {
+                variable,
+                operator,
+                value,
+            } => (
+                format!("{} {} {value}", variable.name(), operator.symbol()),
+                format!("{} -> {result}", variable.value(state)),
+            ),
+            Expression::Delta { variable, amount } => (
+                format!("{} {amount:+}", variable.name()),
+                format!(
+                    "{} (was {}) -> {result}",
+                    variable.value(state),
+                    variable.value(last_state)
+                ),
+            ),
+            Expression::Flag(variable) => (
+                variable.name().to_string(),
+                format!("{} -> {result}", variable.value(state)),
+            ),
+            Expression::Enter(room) | Expression::Exit(room) => (
+                format!(
+                    "{}(\"{room}\")",
+                    if matches!(self, Expression::Enter(_)) {
+                        "enter"
+                    } else {
+                        "exit"
+                    }
+                ),
+                format!(
+                    "{} (was {}) -> {result}",
+                    state.level_name, last_state.level_name
+                ),
+            ),
+            Expression::And(..) | Expression::Or(..) | Expression::Not(_) => {
+                unreachable!("combined conditions are explained by their parts")
+            }
+        }
+    }
+}
+
+/// Writes the expression back as text that parses to the same expression.
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // `&&` binds tighter than `||`, so only an `||` inside another condition needs parentheses
+        let grouped = |expression: &Expression| match expression {
+            Expression::Or(..) => format!("({expression})"),
+            _ => expression.to_string(),
+        };
+        match self {
+            Expression::And(a, b) => write!(f, "{} && {}", grouped(a), grouped(b)),
+            Expression::Or(a, b) => write!(f, "{a} || {b}"),
+            Expression::Not(a) => match **a {
+                Expression::And(..) | Expression::Or(..) => write!(f, "!({a})"),
+                _ => write!(f, "!{a}"),
+            },
+            Expression::Compare {
+                variable,
+                operator,
+                value,
+            } => {
+                write!(f, "{} {} ", variable.name(), operator.symbol())?;
+                match value {
+                    Value::Text(text) => write!(f, "\"{text}\""),
+                    Value::Number(number) => write!(f, "{number}"),
+                    value => write!(f, "{value}"),
+                }
+            }
+            Expression::Delta { variable, amount } => write!(f, "{} {amount:+}", variable.name()),
+            Expression::Flag(variable) => write!(f, "{}", variable.name()),
+            Expression::Enter(room) => write!(f, "enter(\"{room}\")"),
+            Expression::Exit(room) => write!(f, "exit(\"{room}\")"),
+        }
+    }
+}
+
+impl FromStr for Expression {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            position: 0,
+        };
+        let expression = parser.or()?;
+        match parser.peek() {
+            None => Ok(expression),
+            Some(token) => Err(anyhow!("unexpected {token:?} in \"{text}\"")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Identifier(String),
+    Number(String),
+    Text(String),
+    Operator(Operator),
+    And,
+    Or,
+    Not,
+    Plus,
+    Minus,
+    OpenParen,
+    CloseParen,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, c)| c);
+        let token = match (c, next) {
+            (c, _) if c.is_whitespace() => continue,
+            ('&', Some('&'))
+            | ('|', Some('|'))
+            | ('=', Some('='))
+            | ('!', Some('='))
+            | ('<', Some('='))
+            | ('>', Some('=')) => {
+                chars.next();
+                match c {
+                    '&' => Token::And,
+                    '|' => Token::Or,
+                    '=' => Token::Operator(Operator::Equal),
+                    '!' => Token::Operator(Operator::NotEqual),
+                    '<' => Token::Operator(Operator::LessOrEqual),
+                    _ => Token::Operator(Operator::GreaterOrEqual),
+                }
+            }
+            ('<', _) => Token::Operator(Operator::Less),
+            ('>', _) => Token::Operator(Operator::Greater),
+            ('!', _) => Token::Not,
+            ('+', _) => Token::Plus,
+            ('-', _) => Token::Minus,
+            ('(', _) => Token::OpenParen,
+            (')', _) => Token::CloseParen,
+            ('"' | '\'', _) => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, end)) if end == c => break,
+                        Some((_, ch)) => string.push(ch),
+                        None => return Err(anyhow!("unterminated string in \"{text}\"")),
+                    }
+                }
+                Token::Text(string)
+            }
+            // Room names like `c-01` or `9b` are words too, but `-` after a variable is the
+            // operator, like in `berries-1`
+            (c, _) if c.is_alphanumeric() || c == '_' || c == '.' => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(i, ch)) = chars.peek()
+                    && (ch.is_alphanumeric()
+                        || matches!(ch, '_' | '.')
+                        || (ch == '-' && Variable::from_name(&text[start..end]).is_err()))
+                {
+                    end = i + ch.len_utf8();
+                    chars.next();
+                }
+                let word = &text[start..end];
+                match word.to_ascii_lowercase().as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ if word.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+                        && f64::from_str(word).is_ok() =>
+                    {
+                        Token::Number(word.to_string())
+                    }
+                    _ => Token::Identifier(word.to_string()),
+                }
+            }
+            (c, _) => return Err(anyhow!("unexpected '{c}' in \"{text}\"")),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(anyhow!("expected {expected:?}, found {token:?}")),
+            None => Err(anyhow!("expected {expected:?}, found the end of the split")),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expression> {
+        let mut expression = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            expression = Expression::Or(Box::new(expression), Box::new(self.and()?));
+        }
+        Ok(expression)
+    }
+
+    fn and(&mut self) -> Result<Expression> {
+        let mut expression = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            expression = Expression::And(Box::new(expression), Box::new(self.unary()?));
+        }
+        Ok(expression)
+    }
+
+    fn unary(&mut self) -> Result<Expression> {
+        if self.peek() == Some(&Token::Not) {
+            self.next();
+            return Ok(Expression::Not(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expression> {
+        match self.next() {
+            Some(Token::OpenParen) => {
+                let expression = self.or()?;
+                self.expect(Token::CloseParen)?;
+                Ok(expression)
+            }
+            Some(Token::Identifier(name)) if self.peek() == Some(&Token::OpenParen) => {
+                self.next();
+                let room = match self.next() {
+                    Some(Token::Text(room) | Token::Identifier(room) | Token::Number(room)) => room,
+                    _ => return Err(anyhow!("expected a room name in {name}(...)")),
+                };
+                self.expect(Token::CloseParen)?;
+                match name.to_ascii_lowercase().as_str() {
+                    "enter" => Ok(Expression::Enter(room)),
+                    "exit" => Ok(Expression::Exit(room)),
+                    _ => Err(anyhow!("unknown function \"{name}\"")),
+                }
+            }
+            Some(Token::Identifier(name)) => {
+                let variable = Variable::from_name(&name)?;
+                match self.peek().cloned() {
+                    Some(Token::Operator(operator)) => {
+                        self.next();
+                        let token = match self.next() {
+                            // A signed number, like in `level_time > -1`
+                            Some(sign @ (Token::Plus | Token::Minus)) => match self.next() {
+                                Some(Token::Number(number)) if sign == Token::Minus => {
+                                    Token::Number(format!("-{number}"))
+                                }
+                                Some(Token::Number(number)) => Token::Number(number),
+                                _ => {
+                                    return Err(anyhow!(
+                                        "expected a number after the sign in {name} {}",
+                                        operator.symbol()
+                                    ));
+                                }
+                            },
+                            Some(token) => token,
+                            None => return Err(anyhow!("expected a value after {name}")),
+                        };
+                        Ok(Expression::Compare {
+                            variable,
+                            operator,
+                            value: variable.parse_value(&token)?,
+                        })
+                    }
+                    Some(sign @ (Token::Plus | Token::Minus)) => {
+                        self.next();
+                        let amount = match self.next() {
+                            Some(Token::Number(number)) => i64::from_str(&number)
+                                .map_err(|_| anyhow!("invalid amount \"{number}\""))?,
+                            _ => return Err(anyhow!("expected a number after {name} {sign:?}")),
+                        };
+                        Ok(Expression::Delta {
+                            variable,
+                            amount: if sign == Token::Minus {
+                                -amount
+                            } else {
+                                amount
+                            },
+                        })
+                    }
+                    _ if variable.is_flag() => Ok(Expression::Flag(variable)),
+                    _ => Err(anyhow!("{name} needs to be compared to something")),
+                }
+            }
+            Some(token) => Err(anyhow!("unexpected {token:?}")),
+            None => Err(anyhow!("unexpected end of the split")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Expression {
+        Expression::from_str(text).unwrap()
+    }
+
+    fn error(text: &str) -> String {
+        Expression::from_str(text).unwrap_err().to_string()
+    }
+
+    fn state() -> GameState {
+        GameState {
+            area_id: Area::Core,
+            area_difficulty: AreaMode::BSide,
+            level_name: String::from("c-01"),
+            strawberries: 20,
+            heart_gems: 4,
+            level_time: 12.5,
+            chapter_started: true,
+            ..GameState::default()
+        }
+    }
+
+    fn holds(text: &str) -> bool {
+        parse(text).evaluate(&state(), &GameState::default(), None)
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            parse("hearts > 1 || berries == 0 && hearts < 1"),
+            parse("hearts > 1 || (berries == 0 && hearts < 1)")
+        );
+        assert!(holds("hearts > 1 || berries == 0 && hearts < 1"));
+        assert!(!holds("(hearts > 1 || berries == 0) && hearts < 1"));
+        assert!(holds("hearts == 4 and berries == 20 or hearts == 0"));
+    }
+
+    #[test]
+    fn not_applies_to_what_follows() {
+        assert!(holds("!chapter_complete"));
+        assert!(!holds("not chapter_started"));
+        assert!(holds("!(hearts == 4 && berries == 0)"));
+        assert!(!holds("!hearts == 4 || chapter_complete"));
+    }
+
+    #[test]
+    fn comparisons() {
+        assert!(holds("hearts == 4"));
+        assert!(holds("hearts != 5"));
+        assert!(holds("hearts >= 4"));
+        assert!(!holds("hearts > 4"));
+        assert!(holds("level_time < 12.6"));
+        assert!(holds("level_time > -1"));
+        assert!(holds("level_time>=+12.5"));
+        assert!(holds("room == C-01"));
+        assert!(holds("room == \"c-01\""));
+        assert!(holds("chapter_started == true"));
+        // Missing values never compare
+        assert!(!holds("chapter_berries == 0"));
+        assert!(!holds("chapter_berries != 0"));
+    }
+
+    #[test]
+    fn comparing_to_a_side_or_an_area() {
+        assert!(holds("side == B"));
+        assert!(holds("side == bside"));
+        assert!(holds("side > A && side < C"));
+        assert!(holds("area == Core"));
+        assert!(holds("area == 9"));
+        assert!(holds("area > Farewell || area >= TheSummit"));
+        assert!(!holds("area == ForsakenCity"));
+    }
+
+    #[test]
+    fn words_stop_at_operators() {
+        assert_eq!(
+            parse("berries-1"),
+            Expression::Delta {
+                variable: Variable::Berries,
+                amount: -1
+            }
+        );
+        assert_eq!(parse("berries+1"), parse("berries +1"));
+        assert_eq!(parse("room==a-00b"), parse("room == \"a-00b\""));
+        assert!(holds("hearts==4&&room!=c-02"));
+    }
+
+    #[test]
+    fn deltas_compare_to_the_previous_state() {
+        let last = state();
+        let mut current = state();
+        current.strawberries += 1;
+        assert!(parse("berries +1").evaluate(&current, &last, None));
+        assert!(!parse("berries +1").evaluate(&last, &last, None));
+        current.strawberries -= 2;
+        assert!(parse("berries -1").evaluate(&current, &last, None));
+    }
+
+    #[test]
+    fn entering_and_exiting_rooms() {
+        let mut last = state();
+        last.level_name = String::from("c-00");
+        assert!(parse("enter(c-01)").evaluate(&state(), &last, None));
+        assert!(parse("exit(\"C-00\")").evaluate(&state(), &last, None));
+        assert!(!parse("enter(c-01)").evaluate(&state(), &state(), None));
+    }
+
+    #[test]
+    fn written_back_as_text() {
+        for text in [
+            "area == Core && side == B && room == c-01",
+            "(hearts > 1 || berries == 0) && !(chapter_heart || level_time < -1.5)",
+            "berries -1 || !chapter_complete",
+            "enter(\"a 00\") && exit(a-01)",
+        ] {
+            let expression = parse(text);
+            assert_eq!(parse(&expression.to_string()), expression, "{expression}");
+        }
+        assert_eq!(
+            parse("(hearts>1||berries==0)&&room==c-01").to_string(),
+            "(hearts > 1 || berries == 0) && room == \"c-01\""
+        );
+    }
+
+    #[test]
+    fn error_messages() {
+        assert_eq!(error("deaths > 1"), "unknown variable \"deaths\"");
+        assert_eq!(error("hearts"), "hearts needs to be compared to something");
+        assert_eq!(error("hearts =="), "expected a value after hearts");
+        assert_eq!(
+            error("hearts > -"),
+            "expected a number after the sign in hearts >"
+        );
+        assert_eq!(error("hearts > four"), "invalid value \"four\" for hearts");
+        assert_eq!(error("side == D"), "invalid value \"D\" for side");
+        assert_eq!(error("area == Space"), "invalid value \"Space\" for area");
+        assert_eq!(error("berries +"), "expected a number after berries Plus");
+        assert_eq!(
+            error("(hearts > 1"),
+            "expected CloseParen, found the end of the split"
+        );
+        assert_eq!(
+            error("hearts > 1 hearts"),
+            "unexpected Identifier(\"hearts\") in \"hearts > 1 hearts\""
+        );
+        assert_eq!(error("jump(a-00)"), "unknown function \"jump\"");
+        assert_eq!(
+            error("room == \"a-00"),
+            "unterminated string in \"room == \"a-00\""
+        );
+        assert_eq!(
+            error("hearts > 1 # 2"),
+            "unexpected '#' in \"hearts > 1 # 2\""
+        );
+        assert_eq!(error(""), "unexpected end of the split");
+    }
+}
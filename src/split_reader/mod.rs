@@ -1,18 +1,50 @@
+pub mod checkpoints;
+pub mod expression;
+
 use anyhow::Result;
+use log::warn;
 use roxmltree::{Document, NodeId};
 use std::{fs, str::FromStr};
-use strum_macros::EnumString;
+use strum_macros::{EnumIter, EnumMessage, EnumProperty, EnumString, IntoStaticStr};
+
+use expression::Expression;
+pub use linsplit::area::{Area, AreaMode};
 
-#[derive(EnumString, Debug, Clone)]
+/// A split of the splits file. The variants without documentation are described from the
+/// chapter and checkpoint they refer to, see `linsplit list-splits`.
+#[derive(EnumString, EnumIter, EnumMessage, EnumProperty, IntoStaticStr, Debug, Clone)]
 pub enum Split {
+    /// Never splits, the split has to be done from the timer.
     Manual,
+    /// Completing any chapter.
     ChapterA,
-    AreaComplete { area: String },
-    AreaOnEnter { area: String },
-    AreaOnExit { area: String },
+    /// Completing a chapter, or a given side of it.
+    #[strum(props(argument = "Area[-Side]", example = "AreaComplete,Core-BSide"))]
+    AreaComplete {
+        area: String,
+    },
+    /// Entering a chapter, or a given side of it.
+    #[strum(props(argument = "Area[-Side]", example = "AreaOnEnter,Farewell"))]
+    AreaOnEnter {
+        area: String,
+    },
+    /// Leaving a chapter, or a given side of it.
+    #[strum(props(argument = "Area[-Side]", example = "AreaOnExit,OldSite-ASide"))]
+    AreaOnExit {
+        area: String,
+    },
+    /// Collecting any crystal heart.
     HeartGemAny,
-    LevelEnter { level: String },
-    LevelExit { level: String },
+    /// Entering a room, by its name in the map files.
+    #[strum(props(argument = "Room", example = "LevelEnter,b-00"))]
+    LevelEnter {
+        level: String,
+    },
+    /// Leaving a room, by its name in the map files.
+    #[strum(props(argument = "Room", example = "LevelExit,a-00"))]
+    LevelExit {
+        level: String,
+    },
     Prologue,
     Chapter1Checkpoint1,
     Chapter1Checkpoint2,
@@ -76,16 +108,46 @@ pub enum Split {
     Chapter7HeartGem,
     Chapter8Cassette,
     Chapter8HeartGem,
+    /// `Checkpoint,Area,Mode,N`: the `N`th checkpoint of a given side, see [`checkpoints`].
+    #[strum(disabled)]
+    Checkpoint {
+        area: Area,
+        mode: AreaMode,
+        index: u8,
+    },
+    /// A custom condition, see [`Expression`].
+    #[strum(disabled)]
+    Expression {
+        expression: Expression,
+    },
 }
 
 impl Split {
-    fn from_str_field(split: &str) -> Result<Self> {
+    /// Parses the text of a `<Split>` of the splits file.
+    pub fn from_str_field(split: &str) -> Result<Self> {
         if let Ok(split_obj) = Split::from_str(split) {
             return Ok(split_obj);
         }
+        // Custom splits are marked, so that a typo in another split isn't read as a condition
+        if let Some(("Expression", condition)) = split.split_once(',') {
+            return Ok(Split::Expression {
+                expression: Expression::from_str(condition)?,
+            });
+        }
         let sep: Vec<&str> = split.split(",").collect();
+        if let ["Checkpoint", area, mode, index] = sep[..] {
+            let area = Area::from_str(area.trim())?;
+            let mode = AreaMode::from_str(mode.trim())?;
+            let index = index.trim().parse()?;
+            if checkpoints::find(area, mode, index).is_none() {
+                return Err(anyhow::anyhow!(
+                    "there is no checkpoint {index} in {area:?} {mode:?}"
+                ));
+            }
+            return Ok(Split::Checkpoint { area, mode, index });
+        }
         if sep.len() != 2 {
-            return Err(anyhow::anyhow!("wrong split"));
+            return Err(anyhow::anyhow!("wrong split type"));
         }
         if let Some(&split) = sep.first()
             && let Ok(split_obj) = Split::from_str(split)
@@ -103,86 +165,226 @@ impl Split {
             Err(anyhow::anyhow!("wrong split type"))
         }
     }
-}
 
-#[repr(i32)]
-#[derive(PartialEq, Clone, Copy, EnumString, Debug)]
-pub enum Area {
-    #[strum(serialize = "-2")]
-    Unknown = -2,
-    #[strum(serialize = "Menu", serialize = "-1", ascii_case_insensitive)]
-    Menu = -1,
-    #[strum(serialize = "Prologue", serialize = "0", ascii_case_insensitive)]
-    Prologue = 0,
-    #[strum(serialize = "ForsakenCity", serialize = "1", ascii_case_insensitive)]
-    ForsakenCity = 1,
-    #[strum(serialize = "OldSite", serialize = "2", ascii_case_insensitive)]
-    OldSite = 2,
-    #[strum(serialize = "CelestialResort", serialize = "3", ascii_case_insensitive)]
-    CelestialResort = 3,
-    #[strum(serialize = "GoldenRidge", serialize = "4", ascii_case_insensitive)]
-    GoldenRidge = 4,
-    #[strum(serialize = "MirrorTemple", serialize = "5", ascii_case_insensitive)]
-    MirrorTemple = 5,
-    #[strum(serialize = "Reflection", serialize = "6", ascii_case_insensitive)]
-    Reflection = 6,
-    #[strum(serialize = "TheSummit", serialize = "7", ascii_case_insensitive)]
-    TheSummit = 7,
-    #[strum(serialize = "Epilogue", serialize = "8", ascii_case_insensitive)]
-    Epilogue = 8,
-    #[strum(serialize = "Core", serialize = "9", ascii_case_insensitive)]
-    Core = 9,
-    #[strum(serialize = "Farewell", serialize = "10", ascii_case_insensitive)]
-    Farewell = 10,
-}
+    /// The area, side and index of a checkpoint split. The `ChapterNCheckpointM` splits
+    /// don't have a side, they use the checkpoints of whichever side is being played.
+    pub fn checkpoint(&self) -> Option<(Area, Option<AreaMode>, u8)> {
+        let (area, index) = match self {
+            Split::Checkpoint { area, mode, index } => return Some((*area, Some(*mode), *index)),
+            Split::Chapter1Checkpoint1 => (Area::ForsakenCity, 1),
+            Split::Chapter1Checkpoint2 => (Area::ForsakenCity, 2),
+            Split::Chapter2Checkpoint1 => (Area::OldSite, 1),
+            Split::Chapter2Checkpoint2 => (Area::OldSite, 2),
+            Split::Chapter3Checkpoint1 => (Area::CelestialResort, 1),
+            Split::Chapter3Checkpoint2 => (Area::CelestialResort, 2),
+            Split::Chapter3Checkpoint3 => (Area::CelestialResort, 3),
+            Split::Chapter4Checkpoint1 => (Area::GoldenRidge, 1),
+            Split::Chapter4Checkpoint2 => (Area::GoldenRidge, 2),
+            Split::Chapter4Checkpoint3 => (Area::GoldenRidge, 3),
+            Split::Chapter5Checkpoint1 => (Area::MirrorTemple, 1),
+            Split::Chapter5Checkpoint2 => (Area::MirrorTemple, 2),
+            Split::Chapter5Checkpoint3 => (Area::MirrorTemple, 3),
+            Split::Chapter5Checkpoint4 => (Area::MirrorTemple, 4),
+            Split::Chapter6Checkpoint1 => (Area::Reflection, 1),
+            Split::Chapter6Checkpoint2 => (Area::Reflection, 2),
+            Split::Chapter6Checkpoint3 => (Area::Reflection, 3),
+            Split::Chapter6Checkpoint4 => (Area::Reflection, 4),
+            Split::Chapter6Checkpoint5 => (Area::Reflection, 5),
+            Split::Chapter7Checkpoint1 => (Area::TheSummit, 1),
+            Split::Chapter7Checkpoint2 => (Area::TheSummit, 2),
+            Split::Chapter7Checkpoint3 => (Area::TheSummit, 3),
+            Split::Chapter7Checkpoint4 => (Area::TheSummit, 4),
+            Split::Chapter7Checkpoint5 => (Area::TheSummit, 5),
+            Split::Chapter7Checkpoint6 => (Area::TheSummit, 6),
+            Split::Chapter8Checkpoint1 => (Area::Core, 1),
+            Split::Chapter8Checkpoint2 => (Area::Core, 2),
+            Split::Chapter8Checkpoint3 => (Area::Core, 3),
+            Split::Chapter9Checkpoint1 => (Area::Farewell, 1),
+            Split::Chapter9Checkpoint2 => (Area::Farewell, 2),
+            Split::Chapter9Checkpoint3 => (Area::Farewell, 3),
+            Split::Chapter9Checkpoint4 => (Area::Farewell, 4),
+            Split::Chapter9Checkpoint5 => (Area::Farewell, 5),
+            Split::Chapter9Checkpoint6 => (Area::Farewell, 6),
+            Split::Chapter9Checkpoint7 => (Area::Farewell, 7),
+            Split::Chapter9Checkpoint8 => (Area::Farewell, 8),
+            _ => return None,
+        };
+        Some((area, None, index))
+    }
+
+    /// The split completing the chapter `area`, on any side.
+    pub fn chapter_complete(area: Area) -> Option<Split> {
+        match area {
+            Area::Prologue => Some(Split::Prologue),
+            Area::ForsakenCity => Some(Split::Chapter1),
+            Area::OldSite => Some(Split::Chapter2),
+            Area::CelestialResort => Some(Split::Chapter3),
+            Area::GoldenRidge => Some(Split::Chapter4),
+            Area::MirrorTemple => Some(Split::Chapter5),
+            Area::Reflection => Some(Split::Chapter6),
+            Area::TheSummit => Some(Split::Chapter7),
+            Area::Epilogue => Some(Split::Epilogue),
+            Area::Core => Some(Split::Chapter8),
+            Area::Farewell => Some(Split::Chapter9),
+            Area::Unknown | Area::Menu => None,
+        }
+    }
+
+    /// The text of the split in a splits file, the reverse of [`Split::from_str_field`].
+    pub fn to_field(&self) -> String {
+        // The checkpoints and custom splits are disabled for strum, they have no name
+        let name = match self {
+            Split::Checkpoint { area, mode, index } => {
+                return format!("Checkpoint,{area:?},{mode:?},{index}");
+            }
+            Split::Expression { expression } => return format!("Expression,{expression}"),
+            split => <&'static str>::from(split),
+        };
+        match self {
+            Split::AreaComplete { area }
+            | Split::AreaOnEnter { area }
+            | Split::AreaOnExit { area } => format!("{name},{area}"),
+            Split::LevelEnter { level } | Split::LevelExit { level } => format!("{name},{level}"),
+            _ => name.to_string(),
+        }
+    }
 
-#[repr(i32)]
-#[derive(PartialEq, Clone, Copy, EnumString)]
-pub enum AreaMode {
-    #[strum(serialize = "-2")]
-    Unknown = -2,
-    #[strum(serialize = "-1")]
-    None = -1,
-    #[strum(serialize = "ASide", serialize = "0", ascii_case_insensitive)]
-    ASide = 0,
-    #[strum(serialize = "BSide", serialize = "1", ascii_case_insensitive)]
-    BSide = 1,
-    #[strum(serialize = "CSide", serialize = "2", ascii_case_insensitive)]
-    CSide = 2,
+    /// The chapter of the chapter, cassette, heart and checkpoint splits.
+    pub fn area(&self) -> Option<Area> {
+        match self {
+            Split::Prologue => Some(Area::Prologue),
+            Split::Chapter1 | Split::Chapter1Cassette | Split::Chapter1HeartGem => {
+                Some(Area::ForsakenCity)
+            }
+            Split::Chapter2 | Split::Chapter2Cassette | Split::Chapter2HeartGem => {
+                Some(Area::OldSite)
+            }
+            Split::Chapter3 | Split::Chapter3Cassette | Split::Chapter3HeartGem => {
+                Some(Area::CelestialResort)
+            }
+            Split::Chapter4 | Split::Chapter4Cassette | Split::Chapter4HeartGem => {
+                Some(Area::GoldenRidge)
+            }
+            Split::Chapter5 | Split::Chapter5Cassette | Split::Chapter5HeartGem => {
+                Some(Area::MirrorTemple)
+            }
+            Split::Chapter6 | Split::Chapter6Cassette | Split::Chapter6HeartGem => {
+                Some(Area::Reflection)
+            }
+            Split::Chapter7 | Split::Chapter7Cassette | Split::Chapter7HeartGem => {
+                Some(Area::TheSummit)
+            }
+            Split::Epilogue => Some(Area::Epilogue),
+            Split::Chapter8 | Split::Chapter8Cassette | Split::Chapter8HeartGem => Some(Area::Core),
+            Split::Chapter9 => Some(Area::Farewell),
+            _ => self.checkpoint().map(|(area, _, _)| area),
+        }
+    }
 }
 
-impl TryFrom<i32> for Area {
-    type Error = anyhow::Error;
+/// How a run is started, reset and timed.
+#[derive(serde_derive::Deserialize, clap::ValueEnum, PartialEq, Clone, Copy, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum RunKind {
+    /// Starts on a new file and is timed with the file timer.
+    FullGame,
+    /// Starts when entering a chapter and is timed with the chapter timer.
+    IndividualLevel,
+    /// No splits: starts on the first room change and is timed from there.
+    PerRoom,
+    /// No chapter splits: the first split starts the timer, and the run is timed from there.
+    Manual,
+}
 
-    fn try_from(value: i32) -> Result<Self, Self::Error> {
-        match value {
-            -1 => Ok(Area::Menu),
-            0 => Ok(Area::Prologue),
-            1 => Ok(Area::ForsakenCity),
-            2 => Ok(Area::OldSite),
-            3 => Ok(Area::CelestialResort),
-            4 => Ok(Area::GoldenRidge),
-            5 => Ok(Area::MirrorTemple),
-            6 => Ok(Area::Reflection),
-            7 => Ok(Area::TheSummit),
-            8 => Ok(Area::Epilogue),
-            9 => Ok(Area::Core),
-            10 => Ok(Area::Farewell),
-            _ => Ok(Area::Unknown),
+impl RunKind {
+    /// Guesses the kind of run from the splits:
+    /// - no splits at all is a per-room run,
+    /// - no chapter, heart, cassette or area complete splits is a manual run,
+    /// - at most one of each of them is an individual level,
+    /// - anything else is a full game run.
+    pub fn infer(splits: &[Split]) -> Self {
+        let (mut chapters, mut hearts, mut cassettes, mut areas) = (0, 0, 0, 0);
+        for split in splits {
+            match split {
+                Split::ChapterA
+                | Split::Prologue
+                | Split::Chapter1
+                | Split::Chapter2
+                | Split::Chapter3
+                | Split::Chapter4
+                | Split::Chapter5
+                | Split::Chapter6
+                | Split::Chapter7
+                | Split::Epilogue
+                | Split::Chapter8
+                | Split::Chapter9 => chapters += 1,
+                Split::HeartGemAny
+                | Split::Chapter1HeartGem
+                | Split::Chapter2HeartGem
+                | Split::Chapter3HeartGem
+                | Split::Chapter4HeartGem
+                | Split::Chapter5HeartGem
+                | Split::Chapter6HeartGem
+                | Split::Chapter7HeartGem
+                | Split::Chapter8HeartGem => hearts += 1,
+                Split::Chapter1Cassette
+                | Split::Chapter2Cassette
+                | Split::Chapter3Cassette
+                | Split::Chapter4Cassette
+                | Split::Chapter5Cassette
+                | Split::Chapter6Cassette
+                | Split::Chapter7Cassette
+                | Split::Chapter8Cassette => cassettes += 1,
+                Split::AreaComplete { area: _ } => areas += 1,
+                _ => {}
+            }
         }
+        if splits.is_empty() {
+            RunKind::PerRoom
+        } else if chapters + hearts + cassettes + areas == 0 {
+            RunKind::Manual
+        } else if chapters <= 1 && hearts <= 1 && cassettes <= 1 && areas <= 1 {
+            RunKind::IndividualLevel
+        } else {
+            RunKind::FullGame
+        }
+    }
+
+    /// Whether the first split of the list starts the timer, instead of linsplit's own rule.
+    pub fn starts_on_first_split(self) -> bool {
+        self == RunKind::Manual
+    }
+
+    /// Whether the run uses the chapter's timer and flags rather than the file's. It also
+    /// splits as soon as a chapter is completed instead of when leaving it, and can auto reset.
+    pub fn is_chapter_timed(self) -> bool {
+        self != RunKind::FullGame
+    }
+
+    /// Whether the time sent is the time since the timer started rather than the game's timer.
+    pub fn is_relative_to_start(self) -> bool {
+        matches!(self, RunKind::PerRoom | RunKind::Manual)
     }
 }
 
-impl TryFrom<i32> for AreaMode {
-    type Error = anyhow::Error;
+/// Values replacing the ones from the `AutoSplitterSettings` of the splits file.
+#[derive(serde_derive::Deserialize, Default, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct SettingsOverrides {
+    pub auto_reset: Option<bool>,
+    pub set_game_time: Option<bool>,
+    pub file_time_offset: Option<bool>,
+    pub run_kind: Option<RunKind>,
+}
 
-    fn try_from(value: i32) -> Result<Self, Self::Error> {
-        match value {
-            -1 => Ok(AreaMode::None),
-            0 => Ok(AreaMode::ASide),
-            1 => Ok(AreaMode::BSide),
-            2 => Ok(AreaMode::CSide),
-            _ => Ok(AreaMode::Unknown),
+impl SettingsOverrides {
+    /// Fills the overrides missing from `self` with the ones from `fallback`.
+    pub fn or(self, fallback: SettingsOverrides) -> SettingsOverrides {
+        SettingsOverrides {
+            auto_reset: self.auto_reset.or(fallback.auto_reset),
+            set_game_time: self.set_game_time.or(fallback.set_game_time),
+            file_time_offset: self.file_time_offset.or(fallback.file_time_offset),
+            run_kind: self.run_kind.or(fallback.run_kind),
         }
     }
 }
@@ -192,9 +394,10 @@ pub struct SplitData {
     pub auto_reset: bool,
     pub set_game_time: bool,
     pub file_time_offset: bool,
-    pub il_splits: bool,
-    pub chapter_splits: bool,
+    pub run_kind: RunKind,
     pub splits: Vec<Split>,
+    /// Names of the segments of the run, in order.
+    pub segment_names: Vec<String>,
 }
 
 impl SplitData {
@@ -202,13 +405,10 @@ impl SplitData {
         let doc_text = fs::read_to_string(file_path)?;
         let doc = Document::parse(doc_text.as_str())?;
         let mut splits: Vec<Split> = vec![];
+        let mut segment_names: Vec<String> = vec![];
         let mut auto_reset = false;
         let mut set_game_time = false;
         let mut file_time_offset = false;
-        let mut chapter_count = 0;
-        let mut heart_count = 0;
-        let mut area_count = 0;
-        let mut cassette_count = 0;
         for child in doc
             .get_node(NodeId::new(0))
             .unwrap()
@@ -216,6 +416,16 @@ impl SplitData {
             .unwrap()
             .children()
         {
+            if child.tag_name().name() == "Segments" {
+                for segment in child.children().filter(|node| node.has_tag_name("Segment")) {
+                    let name = segment
+                        .children()
+                        .find(|node| node.has_tag_name("Name"))
+                        .and_then(|node| node.text())
+                        .unwrap_or_default();
+                    segment_names.push(String::from(name));
+                }
+            }
             if child.tag_name().name() == "AutoSplitterSettings" {
                 for child2 in child.children() {
                     match child2.tag_name().name() {
@@ -226,17 +436,12 @@ impl SplitData {
                             for split in child2.children() {
                                 if split.tag_name().name() == "Split"
                                     && let Some(split_name) = split.text()
-                                    && let Ok(split_obj) = Split::from_str_field(split_name)
                                 {
-                                    splits.push(split_obj);
-                                    if split_name.len() == 8 {
-                                        chapter_count += 1;
-                                    } else if split_name.contains("HeartGem") {
-                                        heart_count += 1;
-                                    } else if split_name.contains("AreaComplete") {
-                                        area_count += 1;
-                                    } else if split_name.contains("Cassette") {
-                                        cassette_count += 1;
+                                    match Split::from_str_field(split_name) {
+                                        Ok(split_obj) => splits.push(split_obj),
+                                        Err(error) => {
+                                            warn!("Ignoring split \"{split_name}\": {error}")
+                                        }
                                     }
                                 }
                             }
@@ -251,16 +456,86 @@ impl SplitData {
             auto_reset,
             set_game_time,
             file_time_offset,
-            il_splits: splits.is_empty()
-                || (chapter_count <= 1
-                    && heart_count <= 1
-                    && area_count <= 1
-                    && cassette_count <= 1),
-            chapter_splits: chapter_count > 0
-                || heart_count > 0
-                || area_count > 0
-                || cassette_count > 0,
+            run_kind: RunKind::infer(&splits),
             splits,
+            segment_names,
         })
     }
+
+    pub fn apply_overrides(&mut self, overrides: &SettingsOverrides) {
+        if let Some(auto_reset) = overrides.auto_reset {
+            self.auto_reset = auto_reset;
+        }
+        if let Some(set_game_time) = overrides.set_game_time {
+            self.set_game_time = set_game_time;
+        }
+        if let Some(file_time_offset) = overrides.file_time_offset {
+            self.file_time_offset = file_time_offset;
+        }
+        if let Some(run_kind) = overrides.run_kind {
+            self.run_kind = run_kind;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    fn splits(fields: &[&str]) -> Vec<Split> {
+        fields
+            .iter()
+            .map(|field| Split::from_str_field(field).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn infers_the_kind_of_run() {
+        assert_eq!(RunKind::infer(&[]), RunKind::PerRoom);
+        assert_eq!(
+            RunKind::infer(&splits(&["Manual", "Expression,berries >= 5"])),
+            RunKind::Manual
+        );
+        assert_eq!(
+            RunKind::infer(&splits(&[
+                "Chapter1Checkpoint1",
+                "Chapter1Cassette",
+                "Chapter1"
+            ])),
+            RunKind::IndividualLevel
+        );
+        assert_eq!(
+            RunKind::infer(&splits(&["Prologue", "Chapter1", "Chapter2"])),
+            RunKind::FullGame
+        );
+        assert_eq!(
+            RunKind::infer(&splits(&["Chapter1HeartGem", "Chapter2HeartGem"])),
+            RunKind::FullGame
+        );
+    }
+
+    #[test]
+    fn fields_are_read_back() {
+        let fields = [
+            "Checkpoint,OldSite,BSide,2",
+            "Expression,area == ForsakenCity && chapter_complete",
+            "AreaOnEnter,3",
+            "LevelExit,s0",
+        ];
+        let named = Split::iter()
+            .filter(|split| split.checkpoint().is_none_or(|(_, mode, _)| mode.is_none()))
+            .map(|split| split.to_field());
+        for field in named.chain(fields.iter().map(|field| field.to_string())) {
+            let split = Split::from_str_field(&field).unwrap();
+            assert_eq!(split.to_field(), field);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_splits() {
+        assert!(Split::from_str_field("Chapter10").is_err());
+        assert!(Split::from_str_field("Checkpoint,Core,CSide,1").is_err());
+        assert!(Split::from_str_field("Chapter1,3").is_err());
+    }
 }
@@ -0,0 +1,98 @@
+use log::{Level, debug, log_enabled};
+use std::fmt::{Debug, Display, Write};
+
+use crate::split_reader::Split;
+
+/// Inputs and sub-conditions of a split, in the order they're checked. They're recorded by
+/// the code deciding the split, when the decisions are traced.
+#[derive(Default)]
+pub struct Conditions {
+    enabled: bool,
+    entries: Vec<(String, String)>,
+}
+
+impl Conditions {
+    /// Conditions that are only recorded if `enabled`.
+    pub fn new(enabled: bool) -> Self {
+        Conditions {
+            enabled,
+            entries: Vec::new(),
+        }
+    }
+
+    /// The inputs and sub-conditions with their values.
+    pub fn entries(&self) -> &[(String, String)] {
+        &self.entries
+    }
+
+    pub fn add(&mut self, name: &str, value: impl Display) {
+        if self.enabled {
+            self.entries.push((name.to_string(), value.to_string()));
+        }
+    }
+
+    /// A flag the split depends on, returned as is.
+    pub fn check(&mut self, name: &str, value: bool) -> bool {
+        self.add(name, value);
+        value
+    }
+
+    /// A value that has to be equal to `expected`, returns whether it is.
+    pub fn expect<T: Debug + PartialEq>(&mut self, name: &str, value: T, expected: T) -> bool {
+        let result = value == expected;
+        if self.enabled {
+            let ok = if result { "ok" } else { "no" };
+            self.add(name, format!("{value:?}, wants {expected:?} ({ok})"));
+        }
+        result
+    }
+
+    /// A value that has to be different from the previous one, returns whether it is.
+    pub fn changed<T: Debug + PartialEq>(&mut self, name: &str, value: T, previous: T) -> bool {
+        let result = value != previous;
+        if self.enabled {
+            let ok = if result { "ok" } else { "no" };
+            self.add(name, format!("{value:?}, was {previous:?} ({ok})"));
+        }
+        result
+    }
+
+    /// Where the conditions of a custom split are added, if they're recorded.
+    pub fn expression(&mut self) -> Option<&mut Vec<(String, String)>> {
+        self.enabled.then_some(&mut self.entries)
+    }
+}
+
+/// How the split is named in the traces. The conditions of the custom splits are listed
+/// with their values instead.
+pub fn label(split: &Split) -> String {
+    match split {
+        Split::Expression { .. } => String::from("custom split"),
+        split => format!("{split:?}"),
+    }
+}
+
+/// Logs why the current split fires or not, each time something it depends on changes.
+#[derive(Default)]
+pub struct SplitTracer {
+    last: String,
+}
+
+impl SplitTracer {
+    /// Whether the split decisions are traced, with `-v` or `RUST_LOG=split_trace=debug`.
+    pub fn enabled() -> bool {
+        log_enabled!(Level::Debug)
+    }
+
+    pub fn trace(&mut self, segment: &str, split: &str, conditions: &Conditions, splits: bool) {
+        let decision = if splits { "splits" } else { "doesn't split" };
+        let mut explanation = format!("{segment} ({split}) {decision}");
+        for (name, value) in &conditions.entries {
+            let _ = write!(explanation, "\n    {name}: {value}");
+        }
+        if explanation != self.last {
+            debug!("{explanation}");
+            self.last = explanation;
+        }
+    }
+}
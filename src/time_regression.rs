@@ -0,0 +1,114 @@
+use crate::config::TimeRegressionPolicy;
+use crate::split_reader::{Area, AreaMode};
+
+/// The game time going back, from one update to the next.
+pub struct Regression {
+    pub from: f64,
+    pub to: f64,
+}
+
+/// Follows the game time of a run, to notice when it goes backwards: Everest's savestates
+/// and debug teleports can take the file and chapter timers back.
+#[derive(Default)]
+pub struct TimeWatch {
+    /// Game time of the last update.
+    last: Option<f64>,
+    /// Highest game time of the run, that the timer is kept at when clamping.
+    highest: f64,
+    /// Chapter the times were read in, when the chapter's timer is followed.
+    chapter: Option<(Area, AreaMode)>,
+    /// Whether the chapter's timer was started at the last update.
+    started: bool,
+}
+
+impl TimeWatch {
+    /// Forgets the times of the previous run.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Starts over when the chapter changes or its timer is started again, for the runs timed
+    /// with the chapter's timer: it starts again from zero in each chapter, and when the
+    /// chapter is restarted.
+    pub fn enter_chapter(&mut self, area: Area, side: AreaMode, started: bool) {
+        if self.chapter != Some((area, side)) || (started && !self.started) {
+            *self = TimeWatch {
+                chapter: Some((area, side)),
+                ..Self::default()
+            };
+        }
+        self.started = started;
+    }
+
+    /// Follows the game time `time`, and returns the time to send to the timer with the
+    /// regression if the time went back since the last update.
+    pub fn check(&mut self, time: f64, policy: TimeRegressionPolicy) -> (f64, Option<Regression>) {
+        let regression = self
+            .last
+            .filter(|&last| time < last)
+            .map(|from| Regression { from, to: time });
+        self.last = Some(time);
+        self.highest = self.highest.max(time);
+        let time = match policy {
+            TimeRegressionPolicy::Clamp => self.highest,
+            _ => time,
+        };
+        (time, regression)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notices_the_time_going_back() {
+        let mut watch = TimeWatch::default();
+        assert!(watch.check(1., TimeRegressionPolicy::Ignore).1.is_none());
+        assert!(watch.check(2., TimeRegressionPolicy::Ignore).1.is_none());
+        let (time, regression) = watch.check(1.5, TimeRegressionPolicy::Ignore);
+        let regression = regression.unwrap();
+        assert_eq!((time, regression.from, regression.to), (1.5, 2., 1.5));
+        // Only once, going forward again from there is fine
+        assert!(watch.check(1.6, TimeRegressionPolicy::Ignore).1.is_none());
+    }
+
+    #[test]
+    fn clamping_keeps_the_highest_time() {
+        let mut watch = TimeWatch::default();
+        watch.check(5., TimeRegressionPolicy::Clamp);
+        let (time, regression) = watch.check(3., TimeRegressionPolicy::Clamp);
+        assert_eq!(time, 5.);
+        assert!(regression.is_some());
+        assert_eq!(watch.check(4., TimeRegressionPolicy::Clamp).0, 5.);
+        assert_eq!(watch.check(6., TimeRegressionPolicy::Clamp).0, 6.);
+    }
+
+    #[test]
+    fn starts_over_in_each_chapter_and_on_restarts() {
+        let mut watch = TimeWatch::default();
+        watch.enter_chapter(Area::ForsakenCity, AreaMode::ASide, true);
+        watch.check(30., TimeRegressionPolicy::Clamp);
+        // The chapter's timer starts from zero in the next chapter
+        watch.enter_chapter(Area::OldSite, AreaMode::ASide, true);
+        assert!(watch.check(1., TimeRegressionPolicy::Clamp).1.is_none());
+        watch.check(20., TimeRegressionPolicy::Clamp);
+        // And when the chapter is restarted
+        watch.enter_chapter(Area::OldSite, AreaMode::ASide, false);
+        watch.enter_chapter(Area::OldSite, AreaMode::ASide, true);
+        assert_eq!(watch.check(0.5, TimeRegressionPolicy::Clamp).0, 0.5);
+        // But not while it keeps going
+        watch.enter_chapter(Area::OldSite, AreaMode::ASide, true);
+        assert!(watch.check(0.2, TimeRegressionPolicy::Clamp).1.is_some());
+    }
+
+    #[test]
+    fn reset_forgets_the_run() {
+        let mut watch = TimeWatch::default();
+        watch.check(10., TimeRegressionPolicy::Clamp);
+        watch.reset();
+        let (time, regression) = watch.check(1., TimeRegressionPolicy::Clamp);
+        assert_eq!(time, 1.);
+        assert!(regression.is_none());
+    }
+}
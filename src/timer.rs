@@ -0,0 +1,157 @@
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::livesplitone::SplitterSocket;
+use crate::livesplitone::commands::{Command, CommandError, CommandResult, Event, Response};
+use crate::split_trace::Conditions;
+
+/// The timer receiving the split commands.
+pub enum Timer {
+    LiveSplitOne(Arc<SplitterSocket>),
+    /// A timer that only records what it was asked to do, for `linsplit simulate`.
+    Simulated(Arc<SimulatedTimer>),
+}
+
+impl Timer {
+    pub async fn send_command(
+        &self,
+        command: Command,
+    ) -> Result<Option<CommandResult<Response, CommandError>>> {
+        match self {
+            Timer::LiveSplitOne(socket) => socket.send_command(command).await,
+            Timer::Simulated(timer) => {
+                timer.handle(command).await;
+                Ok(Some(CommandResult::Success(Response::None)))
+            }
+        }
+    }
+
+    /// Whether the timer can still receive commands.
+    pub fn is_connected(&self) -> bool {
+        match self {
+            Timer::LiveSplitOne(socket) => socket.is_connected(),
+            Timer::Simulated(_) => true,
+        }
+    }
+
+    pub async fn close(&self) -> Result<()> {
+        match self {
+            Timer::LiveSplitOne(socket) => socket.close().await,
+            Timer::Simulated(_) => Ok(()),
+        }
+    }
+
+    /// Tells the timer why the next split fires. Only the simulated timer keeps it.
+    pub async fn explain_next_split(&self, conditions: Conditions) {
+        if let Timer::Simulated(timer) = self {
+            timer.state.lock().await.reason = Some(conditions);
+        }
+    }
+}
+
+/// Something the simulated timer did.
+pub enum TimerAction {
+    Start {
+        time: f64,
+        conditions: Option<Conditions>,
+    },
+    Split {
+        index: usize,
+        time: f64,
+        conditions: Option<Conditions>,
+    },
+    Reset {
+        /// Split the run was reset on.
+        index: usize,
+        time: f64,
+    },
+}
+
+#[derive(Default)]
+struct SimulatedState {
+    /// Split the run is on, while it's running.
+    current: Option<usize>,
+    finished: bool,
+    game_time: f64,
+    reason: Option<Conditions>,
+    actions: Vec<TimerAction>,
+}
+
+/// Acts like LiveSplit One would: it sends back the same events, without a connection.
+pub struct SimulatedTimer {
+    pub segments: Vec<String>,
+    events: Arc<Mutex<VecDeque<Event>>>,
+    state: Mutex<SimulatedState>,
+}
+
+impl SimulatedTimer {
+    pub fn new(segments: Vec<String>, events: Arc<Mutex<VecDeque<Event>>>) -> Self {
+        SimulatedTimer {
+            segments,
+            events,
+            state: Mutex::default(),
+        }
+    }
+
+    async fn handle(&self, command: Command) {
+        let mut state = self.state.lock().await;
+        let event = match command {
+            Command::SplitOrStart => {
+                let time = state.game_time;
+                let conditions = state.reason.take();
+                match state.current {
+                    _ if state.finished => None,
+                    None => {
+                        state.current = Some(0);
+                        state.actions.push(TimerAction::Start { time, conditions });
+                        Some(Event::Started)
+                    }
+                    Some(index) => {
+                        state.actions.push(TimerAction::Split {
+                            index,
+                            time,
+                            conditions,
+                        });
+                        if index + 1 >= self.segments.len() {
+                            state.current = None;
+                            state.finished = true;
+                            Some(Event::Finished)
+                        } else {
+                            state.current = Some(index + 1);
+                            Some(Event::Splitted)
+                        }
+                    }
+                }
+            }
+            Command::Reset { .. } => {
+                let index = match state.current {
+                    Some(index) => Some(index),
+                    None if state.finished => Some(self.segments.len()),
+                    None => None,
+                };
+                index.map(|index| {
+                    let time = state.game_time;
+                    state.actions.push(TimerAction::Reset { index, time });
+                    state.current = None;
+                    state.finished = false;
+                    Event::Reset
+                })
+            }
+            Command::SetGameTime { time } => {
+                state.game_time = time.to_seconds();
+                None
+            }
+            _ => None,
+        };
+        if let Some(event) = event {
+            self.events.lock().await.push_back(event);
+        }
+    }
+
+    /// Everything the timer did, in order.
+    pub async fn actions(&self) -> Vec<TimerAction> {
+        std::mem::take(&mut self.state.lock().await.actions)
+    }
+}